@@ -31,6 +31,8 @@ class DataCollectorAgent(BaseAgent):
         self.app_usage: Dict[str, Dict[str, Any]] = defaultdict(lambda: {"visits": 0, "total_seconds": 0})
         self.chrome_tabs: List[Dict[str, Any]] = []
         self.tab_usage: Dict[str, Dict[str, Any]] = defaultdict(lambda: {"visits": 0, "total_seconds": 0, "last_title": ""})
+        # Seconds per activity category (meeting, editor, ...) as tagged by the desktop shell
+        self.category_seconds: Dict[str, float] = defaultdict(float)
         self.context_switch_count = 0
         self.last_active_app: Optional[str] = None
         self.last_active_tab_url: Optional[str] = None
@@ -69,7 +71,7 @@ class DataCollectorAgent(BaseAgent):
             return self._check_permissions()
         return None
 
-    def update_activity(self, app_name: str, window_title: str, url: Optional[str] = None):
+    def update_activity(self, app_name: str, window_title: str, url: Optional[str] = None, duration_secs: float = 1.0, category: Optional[str] = None):
         """
         Update activity from external source (Rust sidecar).
         """
@@ -95,9 +97,12 @@ class DataCollectorAgent(BaseAgent):
                         )
                 self.last_active_app = app_name
             
-            # Increment time for current app (assuming called every ~1s)
+            # Increment time for current app by the segment length (1s for per-tick updates)
             if app_name in self.app_usage:
-                self.app_usage[app_name]["total_seconds"] += 1
+                self.app_usage[app_name]["total_seconds"] += duration_secs
+
+            if category:
+                self.category_seconds[category] += duration_secs
 
             # Track Chrome Tab Usage
             if url:
@@ -114,7 +119,7 @@ class DataCollectorAgent(BaseAgent):
                         )
                     self.last_active_tab_url = url
                 
-                self.tab_usage[url]["total_seconds"] += 1.0
+                self.tab_usage[url]["total_seconds"] += duration_secs
                 self.tab_usage[url]["last_title"] = window_title
 
     def set_user(self, user_id: str):
@@ -534,6 +539,7 @@ class DataCollectorAgent(BaseAgent):
             return {
                 "usage": {app: data.copy() for app, data in self.app_usage.items()},
                 "chrome_tabs": self.get_chrome_tabs_data(),
+                "category_seconds": dict(self.category_seconds),
                 "current_status": {
                     "app": current_app,
                     "active_tab_url": self.last_active_tab_url if ("Chrome" in current_app or "Brave" in current_app or "Arc" in current_app) else None,
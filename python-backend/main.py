@@ -16,8 +16,9 @@ import uvicorn
 import time
 import threading
 from collections import defaultdict
-from datetime import datetime, timedelta, date
+from datetime import datetime, timedelta, date, timezone
 import os
+import signal
 import subprocess
 import json
 import glob
@@ -102,12 +103,41 @@ async def lifespan(app: FastAPI):
 app = FastAPI(title="LifeOS Sidecar", version="2.0.0", lifespan=lifespan)
 
 
+def _backend_port() -> int:
+    """Port chosen by the desktop shell, falling back to 14200."""
+    try:
+        port = int(os.environ.get("LIFEOS_BACKEND_PORT", "14200"))
+    except ValueError:
+        return 14200
+    return port if 0 < port < 65536 else 14200
+
+
+BACKEND_PORT = _backend_port()
+
+
+def _user_locale() -> Dict[str, Any]:
+    """Timezone and locale passed in by the desktop shell. The IANA timezone and
+    locale may be missing; the UTC offset is always sent."""
+    try:
+        offset = int(os.environ.get("LIFEOS_UTC_OFFSET_MINUTES", "0"))
+    except ValueError:
+        offset = 0
+    return {
+        "timezone": os.environ.get("LIFEOS_TIMEZONE") or None,
+        "utc_offset_minutes": offset,
+        "locale": os.environ.get("LIFEOS_LOCALE") or None,
+    }
+
+
+USER_LOCALE = _user_locale()
+
+
 # CORS configuration
 app.add_middleware(
     CORSMiddleware,
     allow_origins=[
-        "http://localhost:14200",
-        "http://127.0.0.1:14200",
+        f"http://localhost:{BACKEND_PORT}",
+        f"http://127.0.0.1:{BACKEND_PORT}",
         "http://localhost:1420",
         "http://127.0.0.1:1420",
         "http://localhost:3000",
@@ -131,6 +161,8 @@ class ActivityResponse(BaseModel):
 class HealthResponse(BaseModel):
     """Response model for health endpoint."""
     status: str
+    service: str
+    pid: int
     platform: str
     python_version: str
 
@@ -458,16 +490,73 @@ def reset_usage():
         app_start_time = None
 
 
+# Reported by /health so the desktop shell can tell this backend apart from
+# another service that happens to be on the same port
+SERVICE_MARKER = "life-coach"
+# Bumped whenever a route the desktop shell relies on changes incompatibly
+API_VERSION = 1
+# Optional features this backend offers, so the shell can check before calling them
+CAPABILITIES = [
+    "activity_update",
+    "activity_export",
+    "network_status",
+    "locale",
+    "shutdown",
+]
+
+
+@app.get("/info")
+async def get_info():
+    """Backend version and capabilities, for the desktop shell's startup handshake."""
+    return {
+        "version": app.version,
+        "api_version": API_VERSION,
+        "capabilities": CAPABILITIES,
+    }
+
+
 @app.get("/health", response_model=HealthResponse)
 async def health_check():
     """Health check endpoint to confirm the server is running."""
     return HealthResponse(
         status="healthy",
+        service=SERVICE_MARKER,
+        pid=os.getpid(),
         platform=platform.system(),
         python_version=sys.version.split()[0]
     )
 
 
+class NetworkStatus(BaseModel):
+    online: bool
+
+
+@app.post("/api/network-status")
+async def set_network_status(status: NetworkStatus):
+    """Internet connectivity changes, pushed by the desktop shell."""
+    from services.openai_service import set_online
+    set_online(status.online)
+    return {"status": "ok"}
+
+
+@app.get("/api/locale")
+async def get_locale():
+    """The user's timezone and locale as reported by the desktop shell."""
+    return USER_LOCALE
+
+
+@app.post("/api/shutdown")
+async def shutdown():
+    """
+    Ask the server to shut down cleanly. Used by the desktop shell on Windows,
+    where it can't send SIGTERM.
+    """
+    # Raise SIGINT after the response goes out, so uvicorn runs the normal
+    # lifespan shutdown and the database is closed properly
+    threading.Timer(0.2, signal.raise_signal, args=(signal.SIGINT,)).start()
+    return {"status": "shutting_down"}
+
+
 @app.get("/activity", response_model=ActivityResponse)
 async def get_activity():
     """
@@ -493,6 +582,20 @@ class ActivityUpdate(BaseModel):
     app_name: str
     window_title: str
     url: Optional[str] = None
+    captured_at: Optional[str] = None  # RFC3339, set by the desktop shell at capture time
+    seq: Optional[int] = None  # Increases by one per record within a desktop session
+    # Set when the record covers a segment of time on one window rather than a single tick
+    start: Optional[str] = None
+    end: Optional[str] = None
+    duration_secs: Optional[float] = None
+    category: Optional[str] = None  # meeting, browser, editor, communication or other
+    end_reason: Optional[str] = None  # why the segment ended, e.g. focus_change or shutdown
+    monitor: Optional[Dict[str, Any]] = None  # display index/name and window bounds relative to it
+    focus_session_id: Optional[str] = None  # set while a focus (pomodoro) session is running
+    exe_path: Optional[str] = None  # the focused app's executable, when it could be resolved
+    bundle_id: Optional[str] = None  # macOS bundle identifier, stable across locales and versions
+    source: Optional[str] = None  # "manual" for activities the user logged by hand
+    manual_activity: Optional[str] = None  # label of the manual activity open while this was tracked
 
 @app.post("/api/activity/update")
 def update_activity(activity: ActivityUpdate):
@@ -506,7 +609,9 @@ def update_activity(activity: ActivityUpdate):
     orchestrator.data_collector.update_activity(
         app_name=activity.app_name,
         window_title=activity.window_title,
-        url=activity.url
+        url=activity.url,
+        duration_secs=activity.duration_secs if activity.duration_secs is not None else 1.0,
+        category=activity.category
     )
     return {"status": "ok"}
 
@@ -535,6 +640,51 @@ async def get_activity_logs():
     return {"logs": logs}
 
 
+# Largest page the export endpoint hands out
+MAX_EXPORT_PAGE = 2000
+
+
+def _export_event(row: Dict[str, Any]) -> Dict[str, Any]:
+    """Flatten a stored event into the shape the desktop shell exports (and can re-import)."""
+    try:
+        meta = json.loads(row["metadata"]) if row["metadata"] else {}
+    except (TypeError, ValueError):
+        meta = {}
+    if not isinstance(meta, dict):
+        meta = {}
+
+    # SQLite's CURRENT_TIMESTAMP is UTC without an offset
+    timestamp = datetime.fromisoformat(row["timestamp"])
+    if timestamp.tzinfo is None:
+        timestamp = timestamp.replace(tzinfo=timezone.utc)
+    return {
+        "id": row["id"],
+        "type": row["type"],
+        "timestamp_ms": int(timestamp.timestamp() * 1000),
+        "app_name": meta.get("to"),
+        "window_title": meta.get("title"),
+        "url": meta.get("url"),
+        "metadata": row["metadata"],
+    }
+
+
+@app.get("/api/activity/export")
+async def export_activity(start_ms: Optional[int] = None, end_ms: Optional[int] = None,
+                          after_id: int = 0, limit: int = 500):
+    """
+    Stored activity history, one page at a time in id order. Pass the last id
+    of a page as after_id to get the next; an empty page means the end.
+    """
+    def bound(ms: Optional[int]) -> Optional[str]:
+        if ms is None:
+            return None
+        return datetime.fromtimestamp(ms / 1000, tz=timezone.utc).strftime("%Y-%m-%d %H:%M:%S")
+
+    limit = max(1, min(limit, MAX_EXPORT_PAGE))
+    rows, total = get_database_service().get_events_page(bound(start_ms), bound(end_ms), after_id, limit)
+    return {"events": [_export_event(row) for row in rows], "total": total}
+
+
 @app.get("/metrics")
 async def get_metrics():
     """
@@ -1555,10 +1705,10 @@ async def get_user_stats(user_id: str):
 
 
 if __name__ == "__main__":
-    # Run on port 14200 as specified
+    # Run on the port chosen by the desktop shell (14200 by default)
     uvicorn.run(
         app,
         host="127.0.0.1",
-        port=14200,
+        port=BACKEND_PORT,
         log_level="info"
     )
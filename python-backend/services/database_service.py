@@ -6,7 +6,7 @@ All data stays on user's machine for privacy and performance.
 import sqlite3
 import os
 from pathlib import Path
-from typing import Dict, List, Optional
+from typing import Any, Dict, List, Optional, Tuple
 from datetime import date, datetime
 
 class DatabaseService:
@@ -785,6 +785,42 @@ class DatabaseService:
         finally:
             conn.close()
             
+    def get_events_page(self, start: Optional[str] = None, end: Optional[str] = None,
+                        after_id: int = 0, limit: int = 500) -> Tuple[List[Dict], int]:
+        """
+        One page of stored events in id order, for exports. start/end are UTC
+        'YYYY-MM-DD HH:MM:SS' bounds (end exclusive). Returns (rows, total matching).
+        """
+        conn = sqlite3.connect(self.db_path)
+        conn.row_factory = sqlite3.Row
+        cursor = conn.cursor()
+
+        try:
+            where = ["id > ?"]
+            params: List[Any] = [after_id]
+            if start:
+                where.append("timestamp >= ?")
+                params.append(start)
+            if end:
+                where.append("timestamp < ?")
+                params.append(end)
+
+            cursor.execute(
+                f"SELECT * FROM events WHERE {' AND '.join(where)} ORDER BY id ASC LIMIT ?",
+                (*params, limit)
+            )
+            rows = [dict(row) for row in cursor.fetchall()]
+
+            # Total over the whole range, not just what's left after after_id
+            cursor.execute(
+                f"SELECT COUNT(*) FROM events WHERE {' AND '.join(where[1:]) or '1'}",
+                tuple(params[1:])
+            )
+            total = cursor.fetchone()[0]
+            return rows, total
+        finally:
+            conn.close()
+
     def save_ai_report(self, user_id: str, report_type: str, content: str):
         """Save an AI generated report."""
         conn = sqlite3.connect(self.db_path)
@@ -18,6 +18,19 @@ env_path = os.path.join(project_root, '.env')
 
 load_dotenv(env_path)
 
+# Internet connectivity as reported by the desktop shell. While offline, calls
+# fail fast instead of waiting out the client's timeouts and retries.
+_online = True
+
+
+def set_online(online: bool) -> None:
+    global _online
+    _online = online
+
+
+def is_online() -> bool:
+    return _online
+
 class OpenAIService:
     """
     Centralized service for OpenAI API interactions.
@@ -55,6 +68,8 @@ class OpenAIService:
         Returns:
             Generated text response
         """
+        if not is_online():
+            raise ConnectionError("Offline: skipping OpenAI request")
         try:
             response = await self.client.chat.completions.create(
                 model=self.model_name,
@@ -84,6 +99,8 @@ class OpenAIService:
         Returns:
             Parsed JSON response as dictionary
         """
+        if not is_online():
+            raise ConnectionError("Offline: skipping OpenAI request")
         try:
             response = await self.client.chat.completions.create(
                 model=self.model_name,
@@ -108,6 +125,8 @@ class OpenAIService:
         """
         if not self.api_key:
             return {"nudge_needed": False, "reason": "OpenAI not configured"}
+        if not is_online():
+            return {"nudge_needed": False, "reason": "Offline"}
 
         goal_text = goal.get("goal_text", "Unknown Goal")
         
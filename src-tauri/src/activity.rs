@@ -0,0 +1,437 @@
+use chrono::{DateTime, Local, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+use crate::os_integration::Category;
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// The focused window as reported by get_system_stats and current_activity
+#[derive(Serialize)]
+pub struct ActivitySnapshot {
+    pub app_name: String,
+    pub title: String,
+    // When the tracking loop last captured it, so the UI can show staleness
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captured_at_ms: Option<u64>,
+}
+
+struct LatestActivity {
+    // (app_name, window_title) of the focused window, used to detect changes
+    key: (String, String),
+    changed_at_ms: u64,
+    captured_at_ms: u64,
+    payload: Value,
+}
+
+// Tracks when the focused window last changed so the frontend can long-poll
+// for changes instead of polling on a fixed interval
+#[derive(Default)]
+pub struct ActivityWatch {
+    latest: Mutex<Option<LatestActivity>>,
+    changed: Notify,
+}
+
+impl ActivityWatch {
+    // Records the current activity, waking any waiters if the focused window changed.
+    // Returns whether it changed.
+    pub fn update(&self, app_name: &str, title: &str, payload: Value) -> bool {
+        let key = (app_name.to_string(), title.to_string());
+        let mut latest = self.latest.lock().unwrap();
+
+        match latest.as_mut() {
+            Some(current) if current.key == key => {
+                // Same window, just keep the freshest payload
+                current.payload = payload;
+                current.captured_at_ms = now_ms();
+                false
+            }
+            _ => {
+                let now = now_ms();
+                *latest = Some(LatestActivity {
+                    key,
+                    changed_at_ms: now,
+                    captured_at_ms: now,
+                    payload,
+                });
+                drop(latest);
+                self.changed.notify_waiters();
+                true
+            }
+        }
+    }
+
+    // The last window the tracking loop captured, if any
+    pub fn current(&self) -> Option<ActivitySnapshot> {
+        let latest = self.latest.lock().unwrap();
+        latest.as_ref().map(|current| ActivitySnapshot {
+            app_name: current.key.0.clone(),
+            title: current.key.1.clone(),
+            captured_at_ms: Some(current.captured_at_ms),
+        })
+    }
+
+    // Returns (changed_at_ms, payload) if the focused window changed after `since_ms`
+    pub fn changed_since(&self, since_ms: u64) -> Option<(u64, Value)> {
+        let latest = self.latest.lock().unwrap();
+        latest
+            .as_ref()
+            .filter(|current| current.changed_at_ms > since_ms)
+            .map(|current| (current.changed_at_ms, current.payload.clone()))
+    }
+
+    // Waits up to `timeout` for a change after `since_ms`
+    pub async fn wait_for_change(&self, since_ms: u64, timeout: Duration) -> Option<(u64, Value)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Register interest before checking, so a change in between isn't missed
+            let notified = self.changed.notified();
+            if let Some(change) = self.changed_since(since_ms) {
+                return Some(change);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+}
+
+// Samples further apart than this (or two tracking intervals, if longer) are
+// not treated as continuous focus
+const MAX_SAMPLE_GAP_SECS: i64 = 10;
+
+// A stretch of time during which one app had focus
+#[derive(Clone)]
+pub struct FocusBlock {
+    pub app_name: String,
+    pub category: Category,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+#[derive(Default)]
+struct TimelineInner {
+    day: Option<NaiveDate>,
+    blocks: Vec<FocusBlock>,
+    // The previous day's blocks, kept for a summary that falls due just after midnight
+    previous: Option<(NaiveDate, Vec<FocusBlock>)>,
+}
+
+// Today's focus blocks, built from the tracking loop's samples. Rolled over at local midnight.
+#[derive(Default)]
+pub struct Timeline {
+    inner: Mutex<TimelineInner>,
+}
+
+impl Timeline {
+    // Records that `app_name` had focus at `at`, sampled every `interval_secs`
+    pub fn record(&self, app_name: &str, category: Category, at: DateTime<Local>, interval_secs: u64) {
+        let max_gap = MAX_SAMPLE_GAP_SECS.max(2 * interval_secs as i64);
+        let mut inner = self.inner.lock().unwrap();
+
+        let today = at.date_naive();
+        if inner.day != Some(today) {
+            let finished = inner.day.map(|day| (day, std::mem::take(&mut inner.blocks)));
+            inner.previous = finished;
+            inner.day = Some(today);
+        }
+
+        if let Some(last) = inner.blocks.last_mut() {
+            let continuous = (at - last.end).num_seconds() <= max_gap;
+            if continuous {
+                // Attribute the time up to this sample to the previous window
+                last.end = at;
+                if last.app_name == app_name && last.category == category {
+                    return;
+                }
+            }
+        }
+
+        inner.blocks.push(FocusBlock {
+            app_name: app_name.to_string(),
+            category,
+            start: at,
+            end: at,
+        });
+    }
+
+    pub fn blocks_today(&self) -> Vec<FocusBlock> {
+        self.blocks_on(Local::now().date_naive())
+    }
+
+    // Blocks for `day`, if it is the current or the previous tracked day
+    pub fn blocks_on(&self, day: NaiveDate) -> Vec<FocusBlock> {
+        let inner = self.inner.lock().unwrap();
+        if inner.day == Some(day) {
+            return inner.blocks.clone();
+        }
+        match &inner.previous {
+            Some((previous_day, blocks)) if *previous_day == day => blocks.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HourBucket {
+    pub hour: u32,
+    pub active_seconds: u64,
+    pub top_app: Option<String>,
+}
+
+// Buckets focus blocks into 24 local hours, splitting blocks that cross an hour boundary
+pub fn hourly_breakdown(blocks: &[FocusBlock]) -> Vec<HourBucket> {
+    let mut per_app: Vec<HashMap<&str, u64>> = vec![HashMap::new(); 24];
+
+    for block in blocks {
+        let mut cursor = block.start.num_seconds_from_midnight() as u64;
+        let end = block.end.num_seconds_from_midnight() as u64;
+
+        while cursor < end {
+            let hour = (cursor / 3600) as usize;
+            let hour_end = ((hour as u64) + 1) * 3600;
+            let slice_end = end.min(hour_end);
+            *per_app[hour].entry(block.app_name.as_str()).or_default() += slice_end - cursor;
+            cursor = slice_end;
+        }
+    }
+
+    per_app
+        .into_iter()
+        .enumerate()
+        .map(|(hour, apps)| HourBucket {
+            hour: hour as u32,
+            active_seconds: apps.values().sum(),
+            top_app: apps
+                .into_iter()
+                .max_by_key(|(_, secs)| *secs)
+                .map(|(app, _)| app.to_string()),
+        })
+        .collect()
+}
+
+// Blocks shorter than this are left out of calendar exports
+const MIN_CALENDAR_BLOCK_SECS: i64 = 60;
+
+// Drops sub-minute slivers, then merges neighbouring blocks of the same app, so
+// a quick glance at another app doesn't split one long stretch into many events
+pub fn calendar_blocks(blocks: &[FocusBlock]) -> Vec<FocusBlock> {
+    let mut merged: Vec<FocusBlock> = Vec::new();
+
+    for block in blocks {
+        if (block.end - block.start).num_seconds() < MIN_CALENDAR_BLOCK_SECS {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if last.app_name == block.app_name => last.end = block.end,
+            _ => merged.push(block.clone()),
+        }
+    }
+
+    merged
+}
+
+// Escapes text for an iCalendar TEXT value (RFC 5545 section 3.3.11)
+fn escape_ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Folds a content line to at most 75 octets per line (RFC 5545 section 3.1)
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+fn ics_timestamp(at: &DateTime<Local>) -> String {
+    at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Renders focus blocks as an iCalendar document with one event per block
+pub fn timeline_ics(blocks: &[FocusBlock]) -> String {
+    let stamp = ics_timestamp(&Local::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//LifeOS//Activity Timeline//EN".to_string(),
+    ];
+
+    for block in blocks {
+        let start = ics_timestamp(&block.start);
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}-{}@lifeos", start, block.start.timestamp_subsec_millis()));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", start));
+        lines.push(format!("DTEND:{}", ics_timestamp(&block.end)));
+        lines.push(format!("SUMMARY:{}", escape_ics_text(&block.app_name)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|line| fold_ics_line(line) + "\r\n")
+        .collect()
+}
+
+// One entry of an activity export that failed validation
+#[derive(Serialize)]
+pub struct SkippedEntry {
+    pub index: usize,
+    pub reason: String,
+}
+
+// Validates an activity export, accepting either a bare array of events or an
+// object with an "events" array. Each event needs an app_name and a timestamp_ms;
+// window_title and url are optional. Malformed entries are skipped, not fatal.
+pub fn parse_activity_export(export: &Value) -> Result<(Vec<Value>, Vec<SkippedEntry>), String> {
+    let events = match export {
+        Value::Array(events) => events,
+        Value::Object(obj) => obj
+            .get("events")
+            .and_then(|e| e.as_array())
+            .ok_or("Export has no \"events\" array")?,
+        _ => return Err("Export must be a JSON array or an object with an \"events\" array".to_string()),
+    };
+
+    let mut valid = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        let mut skip = |reason: &str| {
+            skipped.push(SkippedEntry {
+                index,
+                reason: reason.to_string(),
+            })
+        };
+
+        let Some(app_name) = event.get("app_name").and_then(|v| v.as_str()) else {
+            skip("missing app_name");
+            continue;
+        };
+        if app_name.trim().is_empty() {
+            skip("empty app_name");
+            continue;
+        }
+        let Some(timestamp_ms) = event.get("timestamp_ms").and_then(|v| v.as_u64()) else {
+            skip("missing or invalid timestamp_ms");
+            continue;
+        };
+        let optional_text = |key: &str| event.get(key).and_then(|v| v.as_str());
+
+        valid.push(serde_json::json!({
+            "app_name": app_name,
+            "window_title": optional_text("window_title").unwrap_or(""),
+            "url": optional_text("url"),
+            "timestamp_ms": timestamp_ms
+        }));
+    }
+
+    Ok((valid, skipped))
+}
+
+// Formats export_activity can write
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.trim().to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!("Unsupported export format '{}'. Expected csv or json", other)),
+        }
+    }
+}
+
+// Columns of an activity export, in CSV order
+const EXPORT_COLUMNS: [&str; 7] = ["id", "type", "timestamp_ms", "app_name", "window_title", "url", "metadata"];
+
+// Quotes a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+// Writes exported events as they arrive, so an export never has to be held in
+// memory. JSON output is an object with an "events" array, which
+// parse_activity_export reads back.
+pub struct ExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    written: usize,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(mut out: W, format: ExportFormat) -> std::io::Result<Self> {
+        match format {
+            ExportFormat::Csv => writeln!(out, "{}", EXPORT_COLUMNS.join(","))?,
+            ExportFormat::Json => write!(out, "{{\"events\":[")?,
+        }
+        Ok(Self { out, format, written: 0 })
+    }
+
+    pub fn write_event(&mut self, event: &Value) -> std::io::Result<()> {
+        match self.format {
+            ExportFormat::Csv => {
+                let row: Vec<String> = EXPORT_COLUMNS
+                    .iter()
+                    .map(|column| csv_field(event.get(*column).unwrap_or(&Value::Null)))
+                    .collect();
+                writeln!(self.out, "{}", row.join(","))?;
+            }
+            ExportFormat::Json => {
+                if self.written > 0 {
+                    write!(self.out, ",")?;
+                }
+                serde_json::to_writer(&mut self.out, event)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    // Closes the JSON array and flushes; returns how many events were written
+    pub fn finish(mut self) -> std::io::Result<usize> {
+        if self.format == ExportFormat::Json {
+            writeln!(self.out, "]}}")?;
+        }
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
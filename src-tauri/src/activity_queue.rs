@@ -0,0 +1,469 @@
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+
+use crate::{backend, background, dnd, settings, tracking};
+
+// Undelivered records are kept in their own store file so a long outage
+// doesn't bloat settings.json
+const QUEUE_FILE: &str = "activity_queue.json";
+const QUEUE_KEY: &str = "records";
+const ANNOTATIONS_KEY: &str = "annotations";
+
+// Records kept while the backend is unreachable; the oldest go first beyond this
+const MAX_QUEUED: usize = 10_000;
+
+// How often the flusher checks whether queued records can be delivered
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Error responses in a row before the user is told activity isn't being saved
+const POST_FAILURE_THRESHOLD: u32 = 5;
+// Shortest gap between two "not being saved" notifications
+const POST_FAILURE_NOTIFICATION_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+// Where a queued record goes
+#[derive(Clone, Copy, PartialEq)]
+pub enum Endpoint {
+    Activity,
+    Annotation,
+}
+
+impl Endpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Endpoint::Activity => backend::activity_path(),
+            Endpoint::Annotation => "/api/annotations",
+        }
+    }
+
+    fn store_key(self) -> &'static str {
+        match self {
+            Endpoint::Activity => QUEUE_KEY,
+            Endpoint::Annotation => ANNOTATIONS_KEY,
+        }
+    }
+}
+
+// Activity records and annotations that couldn't be delivered, oldest first
+#[derive(Default)]
+pub struct ActivityQueue {
+    // Each record with a sequence number, so a record evicted at the cap
+    // can't be mistaken for the one the flusher is sending
+    records: Mutex<VecDeque<(u64, Endpoint, Value)>>,
+    next_seq: AtomicU64,
+    // Records dropped at the cap since the queue last drained
+    dropped: Mutex<usize>,
+}
+
+impl ActivityQueue {
+    // Restores the records persisted at the last shutdown
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let queue = Self::default();
+        let Ok(store) = app.store(QUEUE_FILE) else {
+            return queue;
+        };
+        for endpoint in [Endpoint::Activity, Endpoint::Annotation] {
+            if let Some(Value::Array(records)) = store.get(endpoint.store_key()) {
+                log::info!("Restored {} undelivered {}", records.len(), endpoint.store_key());
+                for record in records {
+                    queue.push(endpoint, record);
+                }
+                store.delete(endpoint.store_key());
+            }
+        }
+        let _ = store.save();
+        queue
+    }
+
+    fn is_empty(&self) -> bool {
+        self.records.lock().unwrap().is_empty()
+    }
+
+    fn push(&self, endpoint: Endpoint, record: Value) {
+        let mut records = self.records.lock().unwrap();
+        records.push_back((self.next_seq.fetch_add(1, Ordering::Relaxed), endpoint, record));
+        if records.len() > MAX_QUEUED {
+            records.pop_front();
+            let mut dropped = self.dropped.lock().unwrap();
+            if *dropped == 0 {
+                log::warn!("Activity queue is full ({} records), dropping the oldest", MAX_QUEUED);
+            }
+            *dropped += 1;
+        }
+    }
+
+    // The oldest record with its sequence number and destination
+    fn front(&self) -> Option<(u64, Endpoint, Value)> {
+        self.records.lock().unwrap().front().cloned()
+    }
+
+    // Removes the oldest record once it's been dealt with, unless the cap
+    // already evicted it while it was being sent
+    fn remove_front(&self, seq: u64) {
+        let mut records = self.records.lock().unwrap();
+        if records.front().is_some_and(|(front, _, _)| *front == seq) {
+            records.pop_front();
+        }
+    }
+
+    // Writes undelivered records to disk so they survive a restart
+    pub fn persist<R: Runtime>(&self, app: &AppHandle<R>) {
+        let records = self.records.lock().unwrap().clone();
+        if records.is_empty() {
+            return;
+        }
+        let Ok(store) = app.store(QUEUE_FILE) else {
+            log::error!("Failed to open activity queue store, {} records lost", records.len());
+            return;
+        };
+        for endpoint in [Endpoint::Activity, Endpoint::Annotation] {
+            let pending: Vec<Value> = records
+                .iter()
+                .filter(|(_, to, _)| *to == endpoint)
+                .map(|(_, _, record)| record.clone())
+                .collect();
+            if !pending.is_empty() {
+                log::info!("Saving {} undelivered {}", pending.len(), endpoint.store_key());
+                store.set(endpoint.store_key(), pending);
+            }
+        }
+        if let Err(e) = store.save() {
+            log::error!("Failed to save activity queue: {}", e);
+        }
+    }
+}
+
+// Why a record wasn't delivered
+enum SendError {
+    // No answer at all; the backend being down is reported by the supervisor and health monitor
+    Unreachable,
+    // The backend is up but answered with an error status
+    Status(u16),
+}
+
+impl SendError {
+    // Whether the record may go through later. Other client errors mean the
+    // backend will never take it, and retrying would hold up everything queued behind.
+    fn retryable(&self) -> bool {
+        match self {
+            SendError::Unreachable => true,
+            SendError::Status(status) => *status >= 500 || *status == 408 || *status == 429,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PostFailureState {
+    consecutive: u32,
+    alarmed: bool,
+    last_notified: Option<Instant>,
+}
+
+// Notices the backend rejecting activity posts while it is up, e.g. 500s from
+// a broken database, which would otherwise leave the user unaware that nothing
+// is being saved
+#[derive(Default)]
+pub struct PostFailures(Mutex<PostFailureState>);
+
+impl PostFailures {
+    fn record<R: Runtime>(&self, app: &AppHandle<R>, result: &Result<(), SendError>) {
+        let mut state = self.0.lock().unwrap();
+        let status = match result {
+            Ok(()) => {
+                state.consecutive = 0;
+                if std::mem::take(&mut state.alarmed) {
+                    drop(state);
+                    log::info!("The backend is accepting activity again");
+                    let _ = app.emit("tracking-errors", serde_json::json!({ "failing": false }));
+                }
+                return;
+            }
+            Err(SendError::Unreachable) => return,
+            Err(SendError::Status(status)) => *status,
+        };
+        state.consecutive += 1;
+        if state.alarmed || state.consecutive < POST_FAILURE_THRESHOLD {
+            return;
+        }
+        state.alarmed = true;
+        let consecutive = state.consecutive;
+        let cooled_down = state
+            .last_notified
+            .is_none_or(|at| at.elapsed() >= POST_FAILURE_NOTIFICATION_COOLDOWN);
+        let notify = cooled_down
+            && settings::get_bool(app, settings::BACKEND_NOTIFICATIONS, true)
+            && dnd::allows_notification(app, false);
+        if notify {
+            state.last_notified = Some(Instant::now());
+        }
+        drop(state);
+
+        log::error!("The backend rejected {} activity posts in a row (last HTTP {})", consecutive, status);
+        let _ = app.emit(
+            "tracking-errors",
+            serde_json::json!({ "failing": true, "consecutive_failures": consecutive, "status": status }),
+        );
+        if notify {
+            let _ = app
+                .notification()
+                .builder()
+                .title("Activity isn't being saved")
+                .body(if SendError::Status(status).retryable() {
+                    format!(
+                        "The backend is running but returning errors (HTTP {}). Activity is kept and will be retried.",
+                        status
+                    )
+                } else {
+                    format!("The backend is running but rejecting activity (HTTP {}).", status)
+                })
+                .show();
+        }
+    }
+}
+
+// POSTs one record, telling an unreachable backend apart from an error answer
+async fn post_record(client: &reqwest::Client, url: &str, record: &Value) -> Result<(), SendError> {
+    outcome(client.post(url).json(record).send().await)
+}
+
+fn outcome(response: reqwest::Result<reqwest::Response>) -> Result<(), SendError> {
+    match response {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(SendError::Status(response.status().as_u16())),
+        Err(_) => Err(SendError::Unreachable),
+    }
+}
+
+// Logs a record the backend refused for good; it is dropped rather than queued
+fn discard(endpoint: Endpoint, record: &Value, error: &SendError) {
+    if let SendError::Status(status) = error {
+        match endpoint {
+            Endpoint::Activity => log::warn!(
+                "The backend rejected an activity record for {} (HTTP {}), dropping it as undeliverable",
+                record.get("app_name").and_then(Value::as_str).unwrap_or("unknown app"),
+                status
+            ),
+            Endpoint::Annotation => {
+                log::warn!("The backend rejected an annotation (HTTP {}), dropping it as undeliverable", status)
+            }
+        }
+    }
+}
+
+async fn send<R: Runtime>(app: &AppHandle<R>, endpoint: Endpoint, record: &Value) -> Result<(), SendError> {
+    let client = app.state::<reqwest::Client>();
+    let result = post_record(&client, &backend::url(endpoint.path()), record).await;
+    if endpoint == Endpoint::Activity {
+        app.state::<PostFailures>().record(app, &result);
+    }
+    result
+}
+
+// What became of a record handed to post_to
+pub enum Posted {
+    Sent,
+    Queued,
+}
+
+// Delivers a record to `endpoint`, queueing it if the backend is unreachable
+// or failing. While anything is queued new records go behind it, so the
+// backend sees them in order. Err with the status if the backend refused it
+// for good.
+pub async fn post_to<R: Runtime>(app: &AppHandle<R>, endpoint: Endpoint, record: Value) -> Result<Posted, u16> {
+    let queue = app.state::<ActivityQueue>();
+    if queue.is_empty() {
+        match send(app, endpoint, &record).await {
+            Ok(()) => return Ok(Posted::Sent),
+            Err(e @ SendError::Status(status)) if !e.retryable() => {
+                discard(endpoint, &record, &e);
+                return Err(status);
+            }
+            Err(_) => {}
+        }
+    }
+    queue.push(endpoint, record);
+    Ok(Posted::Queued)
+}
+
+// Delivers an activity record through post_to
+pub async fn post<R: Runtime>(app: &AppHandle<R>, record: Value) {
+    let _ = post_to(app, Endpoint::Activity, record).await;
+}
+
+// post for plain threads and the exit handler
+pub fn post_blocking<R: Runtime>(app: &AppHandle<R>, record: Value) {
+    tauri::async_runtime::block_on(post(app, record));
+}
+
+// How long the final send at exit may take before the record is queued instead
+const EXIT_SEND_TIMEOUT: Duration = Duration::from_millis(500);
+
+// One quick delivery attempt for the exit handler, where the backend may be
+// shutting down too. Undelivered records join the queue, which is persisted
+// right after.
+pub fn post_on_exit<R: Runtime>(app: &AppHandle<R>, record: Value) {
+    let queue = app.state::<ActivityQueue>();
+    if queue.is_empty() {
+        let result = tauri::async_runtime::block_on(async {
+            let client = app.state::<reqwest::Client>();
+            let response = client
+                .post(backend::url(backend::activity_path()))
+                .json(&record)
+                .timeout(EXIT_SEND_TIMEOUT)
+                .send()
+                .await;
+            outcome(response)
+        });
+        match result {
+            Ok(()) => return,
+            Err(e) if !e.retryable() => return discard(Endpoint::Activity, &record, &e),
+            Err(_) => {}
+        }
+    }
+    log::info!("Backend unavailable at exit, keeping the last segment for next launch");
+    queue.push(Endpoint::Activity, record);
+}
+
+// Retries queued records in order once /health is green again
+pub fn start_flusher(app: AppHandle) {
+    background::spawn_loop(&app, "queue flusher", FLUSH_INTERVAL, |app| {
+        let queue = app.state::<ActivityQueue>();
+        if queue.is_empty() || !tauri::async_runtime::block_on(tracking::backend_ready(app)) {
+            return;
+        }
+
+        let mut delivered = 0;
+        while let Some((seq, endpoint, record)) = queue.front() {
+            match tauri::async_runtime::block_on(send(app, endpoint, &record)) {
+                Ok(()) => delivered += 1,
+                Err(e) if !e.retryable() => discard(endpoint, &record, &e),
+                // Try again next round, keeping the order
+                Err(_) => break,
+            }
+            queue.remove_front(seq);
+        }
+
+        if delivered > 0 {
+            log::info!("Delivered {} queued activity records", delivered);
+        }
+        if queue.is_empty() {
+            let dropped = std::mem::take(&mut *queue.dropped.lock().unwrap());
+            if dropped > 0 {
+                log::warn!("{} activity records were dropped while the backend was unavailable", dropped);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    // A one-request HTTP server on a free local port. Answers with `status`
+    // and hands over the request line and body it received.
+    fn mock_backend(status: &'static str) -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/activity", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            sender.send((request_line.trim().to_string(), body)).unwrap();
+        });
+        (url, receiver)
+    }
+
+    fn client() -> reqwest::Client {
+        backend::client_builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn posts_the_record_as_json() {
+        let (url, received) = mock_backend("200 OK");
+        let record = serde_json::json!({ "app_name": "Firefox", "window_title": "Inbox", "duration_secs": 12.5 });
+        assert!(post_record(&client(), &url, &record).await.is_ok());
+
+        let (request_line, body) = received.recv().unwrap();
+        assert_eq!(request_line, "POST /api/activity HTTP/1.1");
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), record);
+    }
+
+    #[tokio::test]
+    async fn reports_the_error_status() {
+        let (url, _received) = mock_backend("500 Internal Server Error");
+        let result = post_record(&client(), &url, &serde_json::json!({})).await;
+        assert!(matches!(result, Err(SendError::Status(500))));
+    }
+
+    #[tokio::test]
+    async fn reports_a_closed_port_as_unreachable() {
+        // Bind and drop to find a port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}/api/activity", port);
+        let result = post_record(&client(), &url, &serde_json::json!({})).await;
+        assert!(matches!(result, Err(SendError::Unreachable)));
+    }
+
+    #[test]
+    fn eviction_during_a_send_keeps_the_next_record() {
+        let queue = ActivityQueue::default();
+        queue.push(Endpoint::Activity, serde_json::json!({ "n": 0 }));
+        let (seq, _, _) = queue.front().unwrap();
+        // The cap evicts record 0 while the flusher is sending it
+        for n in 1..=MAX_QUEUED {
+            queue.push(Endpoint::Activity, serde_json::json!({ "n": n }));
+        }
+        queue.remove_front(seq);
+        assert_eq!(queue.records.lock().unwrap().len(), MAX_QUEUED);
+        assert_eq!(queue.front().unwrap().2, serde_json::json!({ "n": 1 }));
+    }
+
+    #[test]
+    fn removes_the_front_once_sent() {
+        let queue = ActivityQueue::default();
+        queue.push(Endpoint::Activity, serde_json::json!({ "n": 0 }));
+        queue.push(Endpoint::Activity, serde_json::json!({ "n": 1 }));
+        let (seq, _, _) = queue.front().unwrap();
+        queue.remove_front(seq);
+        queue.remove_front(seq);
+        assert_eq!(queue.front().unwrap().2, serde_json::json!({ "n": 1 }));
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        for status in [408, 429, 500, 502, 503] {
+            assert!(SendError::Status(status).retryable(), "HTTP {} isn't retried", status);
+        }
+        for status in [400, 401, 404, 413, 422] {
+            assert!(!SendError::Status(status).retryable(), "HTTP {} is retried", status);
+        }
+        assert!(SendError::Unreachable.retryable());
+    }
+}
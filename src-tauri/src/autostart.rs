@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, Runtime};
+
+use crate::{settings, tray};
+
+// Name of the login entry on every platform
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const ENTRY_NAME: &str = "LifeOS";
+#[cfg(target_os = "macos")]
+const LAUNCH_AGENT_LABEL: &str = "com.lifeos.desktop";
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to resolve the app's executable: {}", e))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn home_dir() -> Result<PathBuf, String> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| "HOME is not set".to_string())
+}
+
+// macOS: a LaunchAgent, which shows up under Login Items
+#[cfg(target_os = "macos")]
+fn entry_path() -> Result<PathBuf, String> {
+    Ok(home_dir()?
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCH_AGENT_LABEL)))
+}
+
+#[cfg(target_os = "macos")]
+fn register(exe: &std::path::Path) -> Result<(), String> {
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL,
+        exe.display()
+    );
+    let path = entry_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, plist).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Linux: an XDG autostart entry
+#[cfg(target_os = "linux")]
+fn entry_path() -> Result<PathBuf, String> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .map_or_else(|| home_dir().map(|home| home.join(".config")), Ok)?;
+    Ok(config.join("autostart").join("lifeos.desktop"))
+}
+
+#[cfg(target_os = "linux")]
+fn register(exe: &std::path::Path) -> Result<(), String> {
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\"\nX-GNOME-Autostart-enabled=true\n",
+        ENTRY_NAME,
+        exe.display()
+    );
+    let path = entry_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, entry).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn registered_entry() -> Option<String> {
+    std::fs::read_to_string(entry_path().ok()?).ok()
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn unregister() -> Result<(), String> {
+    let path = entry_path()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+// Windows: a value under the current user's Run key
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(target_os = "windows")]
+fn register(exe: &std::path::Path) -> Result<(), String> {
+    let command = format!("\"{}\"", exe.display());
+    let status = std::process::Command::new("reg")
+        .args(["add", RUN_KEY, "/v", ENTRY_NAME, "/t", "REG_SZ", "/d", &command, "/f"])
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))?
+        .status;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Failed to add the Run registry entry".to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn registered_entry() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", RUN_KEY, "/v", ENTRY_NAME])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn unregister() -> Result<(), String> {
+    if registered_entry().is_none() {
+        return Ok(());
+    }
+    let status = std::process::Command::new("reg")
+        .args(["delete", RUN_KEY, "/v", ENTRY_NAME, "/f"])
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))?
+        .status;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Failed to remove the Run registry entry".to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn register(_exe: &std::path::Path) -> Result<(), String> {
+    Err("Launch at login is not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn registered_entry() -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn unregister() -> Result<(), String> {
+    Ok(())
+}
+
+// Whether an OS login entry for the app exists
+pub fn is_registered() -> bool {
+    registered_entry().is_some()
+}
+
+// Turns launch at login on or off and remembers the choice
+pub fn set_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    if enabled {
+        register(&current_exe()?)?;
+    } else {
+        unregister()?;
+    }
+    tray::sync_autostart_item(app, enabled);
+    settings::set(app, settings::AUTOSTART, enabled)
+}
+
+// Removes the login entry without recording a preference, which is the
+// default once settings are reset
+pub fn reset<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    unregister()?;
+    tray::sync_autostart_item(app, false);
+    Ok(())
+}
+
+// Applies the stored preference at startup. An existing entry is rewritten if
+// it points at a different executable, e.g. after an update moved the app.
+pub fn sync<R: Runtime>(app: &AppHandle<R>) {
+    let Some(enabled) = settings::get(app, settings::AUTOSTART).and_then(|v| v.as_bool()) else {
+        return;
+    };
+    let result = match (enabled, current_exe()) {
+        (true, Ok(exe)) => {
+            let up_to_date = registered_entry().is_some_and(|entry| entry.contains(&exe.display().to_string()));
+            if up_to_date {
+                Ok(())
+            } else {
+                log::info!("Registering launch at login for {}", exe.display());
+                register(&exe)
+            }
+        }
+        (true, Err(e)) => Err(e),
+        (false, _) => unregister(),
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to apply launch at login preference: {}", e);
+    }
+}
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::{os_integration, proxy, settings};
+
+pub const DEFAULT_PORT: u16 = 14200;
+// Environment variable the Python backend reads its port from
+pub const PORT_ENV_VAR: &str = "LIFEOS_BACKEND_PORT";
+// The user's timezone and locale, read by the backend at startup
+pub const TIMEZONE_ENV_VAR: &str = "LIFEOS_TIMEZONE";
+pub const UTC_OFFSET_ENV_VAR: &str = "LIFEOS_UTC_OFFSET_MINUTES";
+pub const LOCALE_ENV_VAR: &str = "LIFEOS_LOCALE";
+
+// Backend routes the shell depends on, overridable via settings for backends
+// that move or version their API
+pub const DEFAULT_ACTIVITY_PATH: &str = "/api/activity/update";
+pub const DEFAULT_HEALTH_PATH: &str = "/health";
+
+// The port from settings, read once at startup
+static PORT: OnceLock<u16> = OnceLock::new();
+// The activity and health paths from settings, read once at startup
+static ACTIVITY_PATH: OnceLock<String> = OnceLock::new();
+static HEALTH_PATH: OnceLock<String> = OnceLock::new();
+// The port the backend actually runs on, when a conflict forced it elsewhere
+static ACTIVE_PORT: AtomicU16 = AtomicU16::new(0);
+
+// How many ports above the configured one to try when it is taken
+const PORT_SEARCH_RANGE: u16 = 20;
+
+// How long a leftover backend gets to exit before it is killed outright
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+// Loads the backend port from the settings store, falling back to the default
+// when it is missing or invalid
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let port = settings::get_u64(app, settings::BACKEND_PORT)
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|p| *p != 0)
+        .unwrap_or(DEFAULT_PORT);
+    let _ = PORT.set(port);
+    let _ = ACTIVITY_PATH.set(configured_path(app, settings::ACTIVITY_PATH, DEFAULT_ACTIVITY_PATH));
+    let _ = HEALTH_PATH.set(configured_path(app, settings::HEALTH_PATH, DEFAULT_HEALTH_PATH));
+}
+
+// A route from settings; must start with "/", otherwise the default is used
+fn configured_path<R: Runtime>(app: &AppHandle<R>, key: &str, default: &str) -> String {
+    let Some(value) = settings::get(app, key) else {
+        return default.to_string();
+    };
+    match value.as_str().map(str::trim) {
+        Some(path) if path.starts_with('/') => path.to_string(),
+        _ => {
+            log::warn!("Ignoring {} setting {}: paths must start with '/'", key, value);
+            default.to_string()
+        }
+    }
+}
+
+// Where activity updates are posted
+pub fn activity_path() -> &'static str {
+    ACTIVITY_PATH.get().map_or(DEFAULT_ACTIVITY_PATH, String::as_str)
+}
+
+// The backend's health check route
+pub fn health_path() -> &'static str {
+    HEALTH_PATH.get().map_or(DEFAULT_HEALTH_PATH, String::as_str)
+}
+
+// The configured port, which the instance lock is derived from
+pub fn configured_port() -> u16 {
+    *PORT.get().unwrap_or(&DEFAULT_PORT)
+}
+
+pub fn port() -> u16 {
+    match ACTIVE_PORT.load(Ordering::Relaxed) {
+        0 => configured_port(),
+        port => port,
+    }
+}
+
+// What is currently listening on a backend port
+#[derive(Debug, PartialEq, Eq)]
+pub enum PortStatus {
+    Free,
+    // A LifeOS backend, e.g. one left behind by an earlier run
+    OurBackend,
+    Taken,
+}
+
+// Checks a port before spawning the backend on it. Speaks plain HTTP over a
+// TcpStream because this runs from both sync and async contexts.
+pub fn probe_port(port: u16) -> PortStatus {
+    let timeout = Duration::from_millis(500);
+    let Ok(mut stream) = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, port).into(), timeout) else {
+        return PortStatus::Free;
+    };
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    let request = format!("GET {} HTTP/1.0\r\nHost: 127.0.0.1:{}\r\n\r\n", health_path(), port);
+    if stream.write_all(request.as_bytes()).is_err() {
+        return PortStatus::Taken;
+    }
+    let mut response = String::new();
+    let _ = stream.take(16 * 1024).read_to_string(&mut response);
+
+    let ok = response.lines().next().is_some_and(|status| status.contains(" 200 "));
+    let body = response.split("\r\n\r\n").nth(1).unwrap_or_default();
+    if ok && is_our_health(body) {
+        PortStatus::OurBackend
+    } else {
+        PortStatus::Taken
+    }
+}
+
+// What our /health reports as "service", so another app answering 200 on the
+// port isn't mistaken for the backend
+pub const SERVICE_MARKER: &str = "life-coach";
+
+// Whether a /health body came from our backend
+pub fn is_our_health(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .is_ok_and(|health| health["service"] == SERVICE_MARKER && health["status"] == "healthy")
+}
+
+// Error for a port that answers /health but isn't our backend
+pub fn port_occupied_error() -> String {
+    format!("Port {} is occupied by another service", port())
+}
+
+// Moves the backend to the first free port above the configured one, skipping
+// the instance lock port. Returns the new port, or None if none was free.
+pub fn move_to_free_port() -> Option<u16> {
+    let configured = configured_port();
+    let port = (1..=PORT_SEARCH_RANGE)
+        .filter_map(|offset| configured.checked_add(offset))
+        .filter(|port| *port != configured.wrapping_add(1))
+        .find(|port| TcpListener::bind((Ipv4Addr::LOCALHOST, *port)).is_ok())?;
+    ACTIVE_PORT.store(port, Ordering::Relaxed);
+    Some(port)
+}
+
+pub fn base_url() -> String {
+    format!("http://127.0.0.1:{}", port())
+}
+
+// Full URL for a backend path such as "/health"
+pub fn url(path: &str) -> String {
+    format!("{}{}", base_url(), path)
+}
+
+// The backend API version this shell was written against
+pub const SUPPORTED_API_VERSION: u32 = 1;
+
+// What the backend reports about itself at /info
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackendInfo {
+    pub version: String,
+    pub api_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl BackendInfo {
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    // Why this backend doesn't match the shell, if it doesn't
+    pub fn incompatibility(&self) -> Option<String> {
+        match self.api_version.cmp(&SUPPORTED_API_VERSION) {
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Less => Some(format!(
+                "Backend {} speaks API v{}, older than the v{} this app expects",
+                self.version, self.api_version, SUPPORTED_API_VERSION
+            )),
+            std::cmp::Ordering::Greater => Some(format!(
+                "Backend {} speaks API v{}, newer than the v{} this app expects",
+                self.version, self.api_version, SUPPORTED_API_VERSION
+            )),
+        }
+    }
+}
+
+// The running backend's /info, fetched once per backend run
+#[derive(Default)]
+pub struct BackendInfoCache(Mutex<Option<BackendInfo>>);
+
+impl BackendInfoCache {
+    pub fn get(&self) -> Option<BackendInfo> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, info: Option<BackendInfo>) {
+        *self.0.lock().unwrap() = info;
+    }
+}
+
+pub async fn fetch_info(client: &reqwest::Client) -> Result<BackendInfo, String> {
+    let response = client
+        .get(url("/info"))
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await
+        .map_err(|e| format!("Backend not responding: {}", e))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        // Backends from before the handshake existed
+        return Err("Backend does not report its version".to_string());
+    }
+    if !status.is_success() {
+        return Err(format!("Backend returned error status: {}", status));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid backend info: {}", e))
+}
+
+// The shared HTTP client for talking to the backend, kept in app state so
+// connections are reused between activity pushes
+pub fn http_client<R: Runtime>(app: &AppHandle<R>) -> reqwest::Client {
+    proxy::apply(app, client_builder()).build().unwrap_or_default()
+}
+
+// A client for backend requests that need their own timeouts, e.g. exports.
+// Never proxied, unlike Client::new, which picks up HTTP_PROXY and friends.
+pub fn local_client() -> reqwest::Client {
+    reqwest::Client::builder().no_proxy().build().unwrap_or_default()
+}
+
+// The backend is local, so a request taking longer than this has hung
+pub fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(1))
+        .timeout(Duration::from_secs(2))
+}
+
+// The backend process we spawned, kept in settings until it stops so one left
+// behind by a crash can be found on the next launch
+#[derive(Serialize, Deserialize)]
+struct BackendRecord {
+    pid: u32,
+    script: PathBuf,
+}
+
+pub fn remember_process<R: Runtime>(app: &AppHandle<R>, pid: u32, script: &Path) {
+    let record = BackendRecord {
+        pid,
+        script: script.to_path_buf(),
+    };
+    let Ok(value) = serde_json::to_value(record) else {
+        return;
+    };
+    if let Err(e) = settings::set(app, settings::BACKEND_PROCESS, value) {
+        log::warn!("Failed to record backend PID: {}", e);
+    }
+}
+
+pub fn forget_process<R: Runtime>(app: &AppHandle<R>) {
+    if settings::get(app, settings::BACKEND_PROCESS).is_some() {
+        let _ = settings::delete(app, settings::BACKEND_PROCESS);
+    }
+}
+
+// Stops a backend left running by an earlier run that didn't shut down
+// cleanly. The recorded PID is only trusted while that process is still
+// running our script; a reused PID belongs to something else and is left alone.
+pub fn kill_orphan<R: Runtime>(app: &AppHandle<R>) {
+    let Some(record) = settings::get(app, settings::BACKEND_PROCESS)
+        .and_then(|value| serde_json::from_value::<BackendRecord>(value).ok())
+    else {
+        return;
+    };
+    forget_process(app);
+
+    let Some(command_line) = os_integration::process_command_line(record.pid) else {
+        return;
+    };
+    if !command_line.contains(record.script.to_string_lossy().as_ref()) {
+        log::debug!("PID {} from the last run is now another program, leaving it alone", record.pid);
+        return;
+    }
+
+    log::warn!("Found backend process {} left over from a previous run, stopping it", record.pid);
+    if os_integration::end_process(record.pid, ORPHAN_GRACE_PERIOD) {
+        log::info!("Stopped orphaned backend process {}", record.pid);
+    } else {
+        log::warn!("Could not stop orphaned backend process {}", record.pid);
+    }
+}
+
+// Environment describing the user's timezone and locale. Values that can't be
+// determined are left out, so the backend falls back to the UTC offset.
+pub fn locale_env() -> Vec<(&'static str, String)> {
+    let info = os_integration::locale_info();
+    let mut env = vec![(UTC_OFFSET_ENV_VAR, info.utc_offset_minutes.to_string())];
+    if let Some(timezone) = info.timezone {
+        env.push((TIMEZONE_ENV_VAR, timezone));
+    }
+    if let Some(locale) = info.locale {
+        env.push((LOCALE_ENV_VAR, locale));
+    }
+    env
+}
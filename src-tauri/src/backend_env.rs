@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::backend;
+
+// Secrets such as LLM API keys are passed to the backend through its
+// environment, never argv, which other processes can read. They live in their
+// own store file rather than settings.json.
+const ENV_FILE: &str = "backend_env.json";
+
+// Variables the shell sets itself and won't let the store override
+const RESERVED: [&str; 4] = [
+    backend::PORT_ENV_VAR,
+    backend::TIMEZONE_ENV_VAR,
+    backend::UTC_OFFSET_ENV_VAR,
+    backend::LOCALE_ENV_VAR,
+];
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(format!(
+            "'{}' is not a valid variable name (use A-Z, 0-9 and _, not starting with a digit)",
+            name
+        ));
+    }
+    if RESERVED.contains(&name) {
+        return Err(format!("{} is set by LifeOS and can't be overridden", name));
+    }
+    Ok(())
+}
+
+// The variables to start the backend with
+pub fn load<R: Runtime>(app: &AppHandle<R>) -> HashMap<String, String> {
+    let Ok(store) = app.store(ENV_FILE) else {
+        return HashMap::new();
+    };
+    store
+        .entries()
+        .into_iter()
+        .filter(|(name, _)| validate_name(name).is_ok())
+        .filter_map(|(name, value)| Some((name, value.as_str()?.to_string())))
+        .collect()
+}
+
+// Names of the stored variables; values are never handed back out
+pub fn names<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    let mut names: Vec<String> = load(app).into_keys().collect();
+    names.sort();
+    names
+}
+
+// Stores a variable, or removes it when `value` is None. Takes effect the next
+// time the backend starts.
+pub fn set<R: Runtime>(app: &AppHandle<R>, name: &str, value: Option<String>) -> Result<(), String> {
+    validate_name(name)?;
+    let store = app
+        .store(ENV_FILE)
+        .map_err(|e| format!("Failed to open backend environment store: {}", e))?;
+    match value {
+        Some(value) => store.set(name, value),
+        None => {
+            store.delete(name);
+        }
+    }
+    store
+        .save()
+        .map_err(|e| format!("Failed to save backend environment: {}", e))?;
+    restrict_permissions(app);
+    Ok(())
+}
+
+// Makes the store readable by the current user only
+#[cfg(unix)]
+fn restrict_permissions<R: Runtime>(app: &AppHandle<R>) {
+    use std::os::unix::fs::PermissionsExt;
+    use tauri::Manager;
+    if let Ok(dir) = app.path().app_data_dir() {
+        let _ = std::fs::set_permissions(dir.join(ENV_FILE), std::fs::Permissions::from_mode(0o600));
+    }
+}
+
+// The per-user app data directory is already private on Windows
+#[cfg(not(unix))]
+fn restrict_permissions<R: Runtime>(_app: &AppHandle<R>) {}
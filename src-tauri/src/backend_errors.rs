@@ -0,0 +1,203 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::activity::now_ms;
+use crate::{dnd, settings};
+
+// How many parsed errors to keep, and how many lines of a single traceback
+const MAX_ERRORS: usize = 50;
+const MAX_LINES_PER_ERROR: usize = 100;
+// Raw stderr lines kept for crash reports
+const STDERR_TAIL_LINES: usize = 50;
+// Shortest gap between two "backend stopped" notifications, so a flapping
+// backend doesn't spam the user
+const DOWN_NOTIFICATION_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Serialize)]
+pub struct BackendError {
+    pub ts_ms: u64,
+    // The exception line for tracebacks, otherwise the log line itself
+    pub summary: String,
+    pub lines: Vec<String>,
+}
+
+// Why the backend process last exited on its own
+#[derive(Clone, Serialize)]
+pub struct BackendCrash {
+    pub ts_ms: u64,
+    pub code: Option<i32>,
+    // Terminating signal on Unix
+    pub signal: Option<i32>,
+    pub last_stderr: Vec<String>,
+}
+
+impl BackendCrash {
+    pub fn from_status(status: std::process::ExitStatus, last_stderr: Vec<String>) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+
+        Self {
+            ts_ms: now_ms(),
+            code: status.code(),
+            signal,
+            last_stderr,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ErrorLogInner {
+    errors: VecDeque<BackendError>,
+    // Traceback currently being collected, line by line
+    pending: Option<BackendError>,
+    stderr_tail: VecDeque<String>,
+    last_crash: Option<BackendCrash>,
+}
+
+// Ring buffer of errors parsed out of the backend's stderr
+#[derive(Default)]
+pub struct BackendErrorLog {
+    inner: Mutex<ErrorLogInner>,
+}
+
+fn is_error_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("ERROR")
+        || trimmed.starts_with("CRITICAL")
+        || trimmed.contains("Exception")
+        || trimmed.contains("Error:")
+}
+
+impl BackendErrorLog {
+    // Feeds one stderr line to the parser. Returns any errors completed by it,
+    // in order, so the caller can announce them.
+    pub fn push_line(&self, line: &str) -> Vec<BackendError> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut completed = Vec::new();
+
+        if inner.stderr_tail.len() == STDERR_TAIL_LINES {
+            inner.stderr_tail.pop_front();
+        }
+        inner.stderr_tail.push_back(line.to_string());
+
+        if let Some(pending) = inner.pending.as_mut() {
+            // Traceback frames are indented; the first unindented line is the exception
+            let is_frame = line.starts_with(' ') || line.starts_with('\t');
+            if pending.lines.len() < MAX_LINES_PER_ERROR {
+                pending.lines.push(line.to_string());
+            }
+            if is_frame {
+                return completed;
+            }
+            let mut error = inner.pending.take().unwrap();
+            error.summary = line.trim().to_string();
+            completed.push(error);
+        } else if line.starts_with("Traceback (most recent call last)") {
+            inner.pending = Some(BackendError {
+                ts_ms: now_ms(),
+                summary: String::new(),
+                lines: vec![line.to_string()],
+            });
+        } else if is_error_line(line) {
+            completed.push(BackendError {
+                ts_ms: now_ms(),
+                summary: line.trim().to_string(),
+                lines: vec![line.to_string()],
+            });
+        }
+
+        for error in &completed {
+            if inner.errors.len() == MAX_ERRORS {
+                inner.errors.pop_front();
+            }
+            inner.errors.push_back(error.clone());
+        }
+        completed
+    }
+
+    // Closes out a traceback cut short by the stream ending
+    pub fn flush(&self) -> Option<BackendError> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut error = inner.pending.take()?;
+        error.summary = "Traceback (incomplete, backend output ended)".to_string();
+        if inner.errors.len() == MAX_ERRORS {
+            inner.errors.pop_front();
+        }
+        inner.errors.push_back(error.clone());
+        Some(error)
+    }
+
+    // The last stderr lines seen, oldest first
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.inner.lock().unwrap().stderr_tail.iter().cloned().collect()
+    }
+
+    pub fn record_crash(&self, crash: BackendCrash) {
+        self.inner.lock().unwrap().last_crash = Some(crash);
+    }
+
+    pub fn last_crash(&self) -> Option<BackendCrash> {
+        self.inner.lock().unwrap().last_crash.clone()
+    }
+
+    // The most recent `n` errors, oldest first
+    pub fn recent(&self, n: usize) -> Vec<BackendError> {
+        let inner = self.inner.lock().unwrap();
+        let skip = inner.errors.len().saturating_sub(n);
+        inner.errors.iter().skip(skip).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+struct DownNotifierState {
+    last_sent: Option<Instant>,
+    awaiting_recovery: bool,
+}
+
+// System notifications for the backend stopping and coming back. Each
+// "stopped" notification gets at most one "recovered" follow-up.
+#[derive(Default)]
+pub struct DownNotifier(Mutex<DownNotifierState>);
+
+impl DownNotifier {
+    pub fn notify_down<R: Runtime>(&self, app: &AppHandle<R>) {
+        if !settings::get_bool(app, settings::BACKEND_NOTIFICATIONS, true) || !dnd::allows_notification(app, false) {
+            return;
+        }
+        let mut state = self.0.lock().unwrap();
+        if state.awaiting_recovery
+            || state.last_sent.is_some_and(|at| at.elapsed() < DOWN_NOTIFICATION_COOLDOWN)
+        {
+            return;
+        }
+        state.last_sent = Some(Instant::now());
+        state.awaiting_recovery = true;
+        let _ = app
+            .notification()
+            .builder()
+            .title("Life Coach backend stopped")
+            .body("Attempting restart…")
+            .show();
+    }
+
+    pub fn notify_recovered<R: Runtime>(&self, app: &AppHandle<R>) {
+        let mut state = self.0.lock().unwrap();
+        if !std::mem::take(&mut state.awaiting_recovery) {
+            return;
+        }
+        if settings::get_bool(app, settings::BACKEND_NOTIFICATIONS, true) && dnd::allows_notification(app, false) {
+            let _ = app
+                .notification()
+                .builder()
+                .title("Backend recovered")
+                .body("The Life Coach backend is running again.")
+                .show();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio_util::sync::CancellationToken;
+
+// Set by shutdown_all; loop threads wait on it between ticks so they stop
+// without finishing a full sleep
+#[derive(Default)]
+struct StopSignal {
+    stopped: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl StopSignal {
+    // Waits up to `timeout`; true once shutdown has started
+    fn wait(&self, timeout: Duration) -> bool {
+        let stopped = self.stopped.lock().unwrap();
+        let (stopped, _) = self.wake.wait_timeout_while(stopped, timeout, |stopped| !*stopped).unwrap();
+        *stopped
+    }
+}
+
+enum Handle {
+    Thread(std::thread::JoinHandle<()>),
+    Async(tauri::async_runtime::JoinHandle<()>),
+}
+
+impl Handle {
+    fn is_finished(&self) -> bool {
+        match self {
+            Handle::Thread(handle) => handle.is_finished(),
+            Handle::Async(handle) => handle.inner().is_finished(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    // False once the task returned or panicked
+    pub alive: bool,
+}
+
+// Every long-running task the shell starts: the supervisor, health monitor,
+// tracking loop, schedulers and monitors. Keeps their handles so their state
+// can be reported, and stops them all together when the app exits.
+#[derive(Default)]
+pub struct BackgroundTasks {
+    stop: Arc<StopSignal>,
+    cancel: CancellationToken,
+    tasks: Mutex<Vec<(&'static str, Handle)>>,
+}
+
+impl BackgroundTasks {
+    fn add(&self, name: &'static str, handle: Handle) {
+        self.tasks.lock().unwrap().push((name, handle));
+    }
+
+    // Stops every task. Loop threads finish their current tick first; nothing is waited on.
+    pub fn shutdown_all(&self) {
+        *self.stop.stopped.lock().unwrap() = true;
+        self.stop.wake.notify_all();
+        self.cancel.cancel();
+        log::info!("Stopping {} background tasks", self.tasks.lock().unwrap().len());
+    }
+
+    pub fn status(&self) -> Vec<TaskStatus> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, handle)| TaskStatus {
+                name,
+                alive: !handle.is_finished(),
+            })
+            .collect()
+    }
+}
+
+// Runs `tick` on its own thread right away and then every `interval` until shutdown
+pub fn spawn_loop<R: Runtime>(
+    app: &AppHandle<R>,
+    name: &'static str,
+    interval: Duration,
+    mut tick: impl FnMut(&AppHandle<R>) + Send + 'static,
+) {
+    let tasks = app.state::<BackgroundTasks>();
+    let stop = tasks.stop.clone();
+    let app = app.clone();
+    let spawned = std::thread::Builder::new().name(name.to_string()).spawn(move || loop {
+        tick(&app);
+        if stop.wait(interval) {
+            break;
+        }
+    });
+    match spawned {
+        Ok(handle) => tasks.add(name, Handle::Thread(handle)),
+        Err(e) => log::error!("Failed to start {}: {}", name, e),
+    }
+}
+
+// Runs an async task on the Tauri runtime. The task gets a token that is
+// cancelled at shutdown and should return soon after.
+pub fn spawn_async<R, F, Fut>(app: &AppHandle<R>, name: &'static str, task: F)
+where
+    R: Runtime,
+    F: FnOnce(AppHandle<R>, CancellationToken) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let tasks = app.state::<BackgroundTasks>();
+    let handle = tauri::async_runtime::spawn(task(app.clone(), tasks.cancel.clone()));
+    tasks.add(name, Handle::Async(handle));
+}
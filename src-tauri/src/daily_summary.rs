@@ -0,0 +1,172 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{activity, background, dnd, settings};
+
+// How often the scheduler checks the clock
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// If the app wasn't running at the configured time, still send the summary
+// when it comes up within this window
+const FIRE_WINDOW_MINUTES: i64 = 30;
+
+// Parses a stored "HH:MM" summary time
+fn summary_time<R: tauri::Runtime>(app: &AppHandle<R>) -> Option<NaiveTime> {
+    let value = settings::get(app, settings::DAILY_SUMMARY_TIME)?;
+    NaiveTime::parse_from_str(value.as_str()?, "%H:%M").ok()
+}
+
+fn format_duration(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+// Builds the summary for `day` from the in-memory focus timeline. The focus
+// score is the percentage of active time spent in productive categories.
+pub fn build_summary(day: NaiveDate, blocks: &[activity::FocusBlock]) -> serde_json::Value {
+    let mut per_app: HashMap<&str, i64> = HashMap::new();
+    let mut productive_secs = 0;
+    for block in blocks {
+        let secs = (block.end - block.start).num_seconds();
+        *per_app.entry(block.app_name.as_str()).or_default() += secs;
+        if block.category.is_productive() {
+            productive_secs += secs;
+        }
+    }
+
+    let total_secs: i64 = per_app.values().sum();
+    let top_app = per_app.into_iter().max_by_key(|(_, secs)| *secs);
+    let focus_score = (total_secs > 0).then(|| (productive_secs * 100 + total_secs / 2) / total_secs);
+
+    serde_json::json!({
+        "date": day.format("%Y-%m-%d").to_string(),
+        "active_seconds": total_secs,
+        "productive_seconds": productive_secs,
+        "focus_score": focus_score,
+        "top_app": top_app.map(|(app, secs)| serde_json::json!({ "app_name": app, "seconds": secs })),
+    })
+}
+
+fn send_summary(app: &AppHandle, day: NaiveDate) {
+    let blocks = app.state::<activity::Timeline>().blocks_on(day);
+    let summary = build_summary(day, &blocks);
+
+    let when = if day == Local::now().date_naive() { "today" } else { "yesterday" };
+    let active = format_duration(summary["active_seconds"].as_i64().unwrap_or(0));
+    let mut body = match summary["top_app"]["app_name"].as_str() {
+        Some(top_app) => format!("Active for {} {}. Most time in {}.", active, when, top_app),
+        None => format!("Active for {} {}.", active, when),
+    };
+    if let Some(score) = summary["focus_score"].as_i64() {
+        body.push_str(&format!(" Focus score {}%.", score));
+    }
+
+    if dnd::allows_notification(app, false) {
+        let _ = app
+            .notification()
+            .builder()
+            .title("Your day in review")
+            .body(body)
+            .show();
+    }
+    // Desktop notifications can't carry a click action, so let the frontend
+    // open the summary view itself
+    let _ = app.emit("daily-summary", summary);
+}
+
+// The day whose summary is due at `now`: the most recent occurrence of
+// `target`, if it was less than FIRE_WINDOW_MINUTES ago. That occurrence may
+// be yesterday's, e.g. a 23:45 summary checked at 00:05.
+fn due_summary_day(now: NaiveDateTime, target: NaiveTime) -> Option<NaiveDate> {
+    let mut due = now.date().and_time(target);
+    if due > now {
+        due -= chrono::Duration::days(1);
+    }
+    ((now - due).num_minutes() < FIRE_WINDOW_MINUTES).then(|| due.date())
+}
+
+// Sends the end-of-day summary notification once per day at the configured time
+pub fn start_scheduler(app: AppHandle) {
+    background::spawn_loop(&app, "daily summary scheduler", CHECK_INTERVAL, |app| {
+        let Some(target) = summary_time(app) else {
+            return;
+        };
+        let Some(day) = due_summary_day(Local::now().naive_local(), target) else {
+            return;
+        };
+        let day_key = day.format("%Y-%m-%d").to_string();
+        let already_sent = settings::get(app, settings::DAILY_SUMMARY_LAST_SENT)
+            .and_then(|v| v.as_str().map(str::to_string))
+            == Some(day_key.clone());
+        if already_sent {
+            return;
+        }
+
+        send_summary(app, day);
+        let _ = settings::set(app, settings::DAILY_SUMMARY_LAST_SENT, day_key);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os_integration::Category;
+    use chrono::TimeZone;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn block(app_name: &str, category: Category, minutes: i64) -> activity::FocusBlock {
+        let start = Local.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        activity::FocusBlock {
+            app_name: app_name.to_string(),
+            category,
+            start,
+            end: start + chrono::Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn fires_within_the_window_after_the_target() {
+        let target = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2);
+        assert_eq!(due_summary_day(at("2026-03-02", "18:00"), target), day);
+        assert_eq!(due_summary_day(at("2026-03-02", "18:29"), target), day);
+        assert_eq!(due_summary_day(at("2026-03-02", "18:30"), target), None);
+        assert_eq!(due_summary_day(at("2026-03-02", "17:59"), target), None);
+    }
+
+    #[test]
+    fn a_late_evening_summary_still_fires_just_after_midnight() {
+        let target = NaiveTime::from_hms_opt(23, 45, 0).unwrap();
+        assert_eq!(
+            due_summary_day(at("2026-03-03", "00:05"), target),
+            NaiveDate::from_ymd_opt(2026, 3, 2)
+        );
+        assert_eq!(due_summary_day(at("2026-03-03", "00:15"), target), None);
+    }
+
+    #[test]
+    fn focus_score_is_the_productive_share_of_active_time() {
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let blocks = [
+            block("Code", Category::Editor, 30),
+            block("Zoom", Category::Meeting, 15),
+            block("Slack", Category::Communication, 15),
+        ];
+        let summary = build_summary(day, &blocks);
+        assert_eq!(summary["focus_score"], 75);
+        assert_eq!(summary["productive_seconds"], 45 * 60);
+        assert_eq!(summary["top_app"]["app_name"], "Code");
+
+        assert!(build_summary(day, &[]).get("focus_score").unwrap().is_null());
+    }
+}
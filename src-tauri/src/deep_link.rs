@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+use crate::main_window;
+
+// Scheme registered for the app in tauri.conf.json
+const SCHEME: &str = "lifeos";
+
+#[derive(Clone, Serialize)]
+pub struct DeepLinkRoute {
+    pub url: String,
+    // Frontend route, e.g. lifeos://session/123?tab=notes -> /session/123?tab=notes
+    pub route: String,
+}
+
+// A link that arrived before the frontend was listening (cold start)
+pub struct PendingDeepLink(Mutex<Option<DeepLinkRoute>>);
+
+// Maps a lifeos:// URL to a frontend route. The host is the first path
+// segment, since lifeos://session/123 parses with "session" as the host.
+pub fn route_for(url: &Url) -> Option<String> {
+    if url.scheme() != SCHEME {
+        return None;
+    }
+    let mut route = format!("/{}{}", url.host_str().unwrap_or(""), url.path());
+    if route.len() > 1 && route.ends_with('/') {
+        route.pop();
+    }
+    if let Some(query) = url.query() {
+        route.push('?');
+        route.push_str(query);
+    }
+    Some(route)
+}
+
+// Brings the main window forward and tells the frontend where to navigate.
+// Returns the route that was sent.
+pub fn handle<R: Runtime>(app: &AppHandle<R>, urls: Vec<Url>) -> Option<DeepLinkRoute> {
+    // Only the last link matters if several arrive at once
+    let url = urls.into_iter().last()?;
+    let Some(route) = route_for(&url) else {
+        log::warn!("Ignoring deep link with unexpected scheme: {}", url);
+        return None;
+    };
+    log::info!("Deep link {} -> {}", url, route);
+
+    let link = DeepLinkRoute {
+        url: url.to_string(),
+        route,
+    };
+
+    main_window::show(app);
+    let _ = app.emit("deep-link", &link);
+    Some(link)
+}
+
+// Re-sends a link that arrived before the page loaded, then forgets it
+pub fn replay_pending<R: Runtime>(app: &AppHandle<R>) {
+    let Some(pending) = app.try_state::<PendingDeepLink>() else {
+        return;
+    };
+    let link = pending.0.lock().unwrap().take();
+    if let Some(link) = link {
+        let _ = app.emit("deep-link", &link);
+    }
+}
+
+// Listens for links while running and handles the one the app was launched with
+pub fn register<R: Runtime>(app: &AppHandle<R>) {
+    // Installed builds register the scheme at install time; dev builds have to do it themselves
+    #[cfg(all(debug_assertions, any(target_os = "linux", target_os = "windows")))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("Failed to register deep link scheme: {}", e);
+    }
+
+    let app_handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        handle(&app_handle, event.urls());
+    });
+
+    // The frontend isn't listening yet on a cold start, so keep the link for replay
+    let launch_link = match app.deep_link().get_current() {
+        Ok(Some(urls)) => handle(app, urls),
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("Failed to read launch deep link: {}", e);
+            None
+        }
+    };
+    app.manage(PendingDeepLink(Mutex::new(launch_link)));
+}
@@ -0,0 +1,74 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::{activity, settings, tray};
+
+// Longest do-not-disturb period that can be set to expire on its own
+pub const MAX_DND_MINUTES: u64 = 24 * 60;
+
+// Do-not-disturb: tracking carries on, but notifications are held back
+#[derive(Clone, Copy, Serialize)]
+pub struct DndState {
+    pub enabled: bool,
+    // When it switches itself off; None means it stays on until turned off
+    pub until_ms: Option<u64>,
+}
+
+pub fn state<R: Runtime>(app: &AppHandle<R>) -> DndState {
+    let until_ms = settings::get_u64(app, settings::DND_UNTIL_MS);
+    let expired = until_ms.is_some_and(|until| activity::now_ms() >= until);
+    let enabled = settings::get_bool(app, settings::DND, false) && !expired;
+    DndState {
+        enabled,
+        until_ms: until_ms.filter(|_| enabled),
+    }
+}
+
+// Whether a notification may be shown. Critical ones (e.g. the backend failing
+// for good) break through do-not-disturb if the user allowed that.
+pub fn allows_notification<R: Runtime>(app: &AppHandle<R>, critical: bool) -> bool {
+    !state(app).enabled || (critical && settings::get_bool(app, settings::DND_ALLOW_CRITICAL, false))
+}
+
+// Turns do-not-disturb on or off, optionally expiring after `minutes`
+pub fn set<R: Runtime>(app: &AppHandle<R>, enabled: bool, minutes: Option<u64>) -> Result<(), String> {
+    let until_ms = minutes
+        .filter(|_| enabled)
+        .map(|minutes| activity::now_ms() + minutes * 60_000);
+    match until_ms {
+        Some(until) => settings::set(app, settings::DND_UNTIL_MS, until)?,
+        None => settings::delete(app, settings::DND_UNTIL_MS)?,
+    }
+    settings::set(app, settings::DND, enabled)?;
+    log::info!("Do not disturb {}", if enabled { "on" } else { "off" });
+    apply(app);
+    Ok(())
+}
+
+// Brings the tray and frontend in line with the stored state and arms the
+// expiry timer. Run at startup and whenever the state changes.
+pub fn apply<R: Runtime>(app: &AppHandle<R>) {
+    let state = state(app);
+    if !state.enabled && settings::get_bool(app, settings::DND, false) {
+        // Expired while the app wasn't running
+        let _ = settings::delete(app, settings::DND_UNTIL_MS);
+        let _ = settings::set(app, settings::DND, false);
+    }
+    tray::sync_dnd_item(app, state.enabled);
+    let _ = app.emit("dnd-changed", state);
+
+    if let Some(until) = state.until_ms {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(until.saturating_sub(activity::now_ms()))).await;
+            // Only if this is still the period that was set, not a later one
+            if settings::get_u64(&app, settings::DND_UNTIL_MS) == Some(until) {
+                log::info!("Do not disturb expired");
+                if let Err(e) = set(&app, false, None) {
+                    log::warn!("Failed to turn off do not disturb: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+
+use crate::settings;
+
+// Shared deadline for all enrichers of one record; anything still running is killed
+const ENRICHER_TIMEOUT: Duration = Duration::from_secs(1);
+// How often a running enricher is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Larger output is drained and rejected
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+// A user script that adds context to activity records, e.g. the current git
+// branch. It gets the record as JSON on stdin and prints a JSON object whose
+// fields are added to the record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Enricher {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+fn spawn(enricher: &Enricher, input: &str) -> Result<Child, String> {
+    let mut command = Command::new(&enricher.command);
+    command
+        .args(&enricher.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command.spawn().map_err(|e| format!("failed to start: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Scripts that don't read their input close the pipe; that's fine
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    Ok(child)
+}
+
+// Reads an enricher's output while it runs, so one printing more than the
+// pipe holds doesn't stall until it's killed
+fn read_output(mut stdout: ChildStdout) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    (&mut stdout)
+        .take(MAX_OUTPUT_BYTES + 1)
+        .read_to_end(&mut output)
+        .map_err(|e| format!("unreadable output: {}", e))?;
+    if output.len() as u64 > MAX_OUTPUT_BYTES {
+        let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+        return Err(format!("output is larger than {} bytes", MAX_OUTPUT_BYTES));
+    }
+    Ok(output)
+}
+
+// Waits for an enricher until `deadline` and parses what it printed
+fn collect(mut child: Child, deadline: Instant) -> Result<Map<String, Value>, String> {
+    // Not joined: something the enricher started may keep the pipe open
+    let (sender, receiver) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        std::thread::spawn(move || {
+            let _ = sender.send(read_output(stdout));
+        });
+    }
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err("timed out".to_string());
+            }
+            Err(e) => return Err(format!("failed to wait: {}", e)),
+        }
+    };
+    if !status.success() {
+        return Err(format!("exited with {}", status));
+    }
+
+    let output = match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(output) => output?,
+        Err(RecvTimeoutError::Timeout) => return Err("timed out".to_string()),
+        Err(RecvTimeoutError::Disconnected) => Vec::new(),
+    };
+    match serde_json::from_slice(&output) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        _ => Err("output is not a JSON object".to_string()),
+    }
+}
+
+// Runs the configured enrichers side by side and adds their fields to the
+// record. Fields already in the record are never overwritten. A failing or
+// slow enricher is logged and skipped.
+pub fn enrich<R: Runtime>(app: &AppHandle<R>, payload: &mut Value) {
+    let enrichers = settings::enrichers(app);
+    if enrichers.is_empty() {
+        return;
+    }
+
+    let input = payload.to_string();
+    let deadline = Instant::now() + ENRICHER_TIMEOUT;
+    let running: Vec<_> = enrichers
+        .iter()
+        .filter_map(|enricher| match spawn(enricher, &input) {
+            Ok(child) => Some((enricher, child)),
+            Err(e) => {
+                log::warn!("Skipping enricher {}: {}", enricher.name, e);
+                None
+            }
+        })
+        .collect();
+
+    for (enricher, child) in running {
+        let fields = match collect(child, deadline) {
+            Ok(fields) => fields,
+            Err(e) => {
+                log::warn!("Skipping enricher {}: {}", enricher.name, e);
+                continue;
+            }
+        };
+        for (key, value) in fields {
+            if payload.get(&key).is_some() {
+                log::debug!("Enricher {} tried to replace {}, ignoring", enricher.name, key);
+                continue;
+            }
+            payload[key.as_str()] = value;
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn run(script: &str) -> Result<Map<String, Value>, String> {
+        let enricher = Enricher {
+            name: "test".to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        };
+        let child = spawn(&enricher, r#"{"app_name":"Code"}"#)?;
+        collect(child, Instant::now() + ENRICHER_TIMEOUT)
+    }
+
+    #[test]
+    fn parses_the_printed_object() {
+        let fields = run(r#"cat >/dev/null; echo '{"branch": "main"}'"#).unwrap();
+        assert_eq!(fields["branch"], "main");
+    }
+
+    #[test]
+    fn rejects_output_over_the_limit_without_stalling() {
+        // Far more than a pipe buffer; the enricher must not block on its write
+        let error = run(r#"head -c 1000000 /dev/zero | tr '\0' ' '; echo '{}'"#).unwrap_err();
+        assert!(error.contains("larger than"), "{}", error);
+    }
+
+    #[test]
+    fn reports_failures() {
+        assert_eq!(run("echo '[1, 2]'").unwrap_err(), "output is not a JSON object");
+        assert!(run("exit 3").unwrap_err().starts_with("exited with"));
+        assert_eq!(run("sleep 5").unwrap_err(), "timed out");
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{activity, background, dnd, settings};
+
+pub const FOCUS_MINUTES_RANGE: std::ops::RangeInclusive<u64> = 1..=240;
+// How long before the end the "almost done" notification comes
+const WARNING_BEFORE_MS: u64 = 60_000;
+// How often the timer checks the running session
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// A session that ended while the app was closed is only announced if it ended at most this long ago
+const MISSED_GRACE_MS: u64 = 30 * 60_000;
+
+// Serializes read-modify-write of the stored session between commands and the timer
+static FOCUS_LOCK: Mutex<()> = Mutex::new(());
+// Disambiguates ids created in the same millisecond
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+// A timed focus (pomodoro) session. Stored in settings so a restart picks it up again.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub id: String,
+    pub minutes: u64,
+    pub started_at_ms: u64,
+    // When a running session ends; None while paused
+    pub ends_at_ms: Option<u64>,
+    // Time left when it was last paused
+    pub remaining_ms: u64,
+    // Whether the "almost done" notification went out
+    #[serde(default)]
+    pub warned: bool,
+}
+
+impl FocusSession {
+    fn remaining_at(&self, now_ms: u64) -> u64 {
+        match self.ends_at_ms {
+            Some(end) => end.saturating_sub(now_ms),
+            None => self.remaining_ms,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FocusStatus {
+    // "idle", "running" or "paused"
+    pub state: &'static str,
+    pub session: Option<FocusSession>,
+    pub remaining_ms: u64,
+}
+
+fn load<R: Runtime>(app: &AppHandle<R>) -> Option<FocusSession> {
+    settings::get(app, settings::FOCUS_SESSION).and_then(|session| serde_json::from_value(session).ok())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, session: Option<&FocusSession>) -> Result<(), String> {
+    match session {
+        Some(session) => {
+            let session = serde_json::to_value(session).map_err(|e| e.to_string())?;
+            settings::set(app, settings::FOCUS_SESSION, session)
+        }
+        None => settings::delete(app, settings::FOCUS_SESSION),
+    }
+}
+
+fn status_of(session: Option<FocusSession>) -> FocusStatus {
+    let state = match &session {
+        None => "idle",
+        Some(session) if session.ends_at_ms.is_some() => "running",
+        Some(_) => "paused",
+    };
+    FocusStatus {
+        state,
+        remaining_ms: session.as_ref().map_or(0, |session| session.remaining_at(activity::now_ms())),
+        session,
+    }
+}
+
+pub fn status<R: Runtime>(app: &AppHandle<R>) -> FocusStatus {
+    status_of(load(app))
+}
+
+// The id of the session running right now, for tagging activity records
+pub fn running_id<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    load(app).filter(|session| session.ends_at_ms.is_some()).map(|session| session.id)
+}
+
+// Saves the new state and tells the frontend
+fn update<R: Runtime>(app: &AppHandle<R>, session: Option<FocusSession>) -> Result<FocusStatus, String> {
+    save(app, session.as_ref())?;
+    let status = status_of(session);
+    let _ = app.emit("focus-session-changed", &status);
+    Ok(status)
+}
+
+pub fn start<R: Runtime>(app: &AppHandle<R>, minutes: u64) -> Result<FocusStatus, String> {
+    if !FOCUS_MINUTES_RANGE.contains(&minutes) {
+        return Err(format!(
+            "Focus sessions must be {}-{} minutes, got {}",
+            FOCUS_MINUTES_RANGE.start(),
+            FOCUS_MINUTES_RANGE.end(),
+            minutes
+        ));
+    }
+    let _guard = FOCUS_LOCK.lock().unwrap();
+    if load(app).is_some() {
+        return Err("A focus session is already in progress".to_string());
+    }
+    let now = activity::now_ms();
+    let session = FocusSession {
+        id: format!("focus-{}-{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+        minutes,
+        started_at_ms: now,
+        ends_at_ms: Some(now + minutes * 60_000),
+        remaining_ms: minutes * 60_000,
+        warned: false,
+    };
+    log::info!("Focus session {} started for {} minutes", session.id, minutes);
+    update(app, Some(session))
+}
+
+pub fn pause<R: Runtime>(app: &AppHandle<R>) -> Result<FocusStatus, String> {
+    let _guard = FOCUS_LOCK.lock().unwrap();
+    let mut session = load(app).ok_or("No focus session in progress")?;
+    let Some(end) = session.ends_at_ms.take() else {
+        return Err("The focus session is already paused".to_string());
+    };
+    session.remaining_ms = end.saturating_sub(activity::now_ms());
+    log::info!("Focus session {} paused", session.id);
+    update(app, Some(session))
+}
+
+pub fn resume<R: Runtime>(app: &AppHandle<R>) -> Result<FocusStatus, String> {
+    let _guard = FOCUS_LOCK.lock().unwrap();
+    let mut session = load(app).ok_or("No focus session in progress")?;
+    if session.ends_at_ms.is_some() {
+        return Err("The focus session is already running".to_string());
+    }
+    session.ends_at_ms = Some(activity::now_ms() + session.remaining_ms);
+    log::info!("Focus session {} resumed", session.id);
+    update(app, Some(session))
+}
+
+pub fn cancel<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let _guard = FOCUS_LOCK.lock().unwrap();
+    let session = load(app).ok_or("No focus session in progress")?;
+    log::info!("Focus session {} cancelled", session.id);
+    update(app, None)?;
+    let _ = app.emit("focus-session-ended", serde_json::json!({ "session": session, "completed": false }));
+    Ok(())
+}
+
+fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+// Sends the pre-end warning and finishes the session once its time is up
+fn check<R: Runtime>(app: &AppHandle<R>) {
+    let _guard = FOCUS_LOCK.lock().unwrap();
+    let Some(mut session) = load(app) else {
+        return;
+    };
+    let Some(end) = session.ends_at_ms else {
+        return;
+    };
+    let now = activity::now_ms();
+
+    if now < end {
+        // Only sessions long enough to have an "almost done" moment get a warning
+        let due = end - now <= WARNING_BEFORE_MS && session.minutes * 60_000 > 2 * WARNING_BEFORE_MS;
+        if due && !session.warned {
+            session.warned = true;
+            if dnd::allows_notification(app, false) {
+                notify(app, "Focus session almost done", "One minute to go.");
+            }
+            if let Err(e) = save(app, Some(&session)) {
+                log::warn!("Failed to save focus session: {}", e);
+            }
+        }
+        return;
+    }
+
+    if let Err(e) = update(app, None) {
+        log::warn!("Failed to clear finished focus session: {}", e);
+    }
+    if now - end > MISSED_GRACE_MS {
+        log::info!("Focus session {} ended while LifeOS was closed", session.id);
+        return;
+    }
+    log::info!("Focus session {} completed", session.id);
+    // The end of a session always gets through, do not disturb or not
+    notify(
+        app,
+        "Focus session complete",
+        &format!("{} minutes of focus done. Time for a break.", session.minutes),
+    );
+    let _ = app.emit("focus-session-ended", serde_json::json!({ "session": session, "completed": true }));
+}
+
+// Runs the focus session timer, independent of the frontend. A session left
+// running by a previous launch carries on where it was.
+pub fn start_timer<R: Runtime>(app: AppHandle<R>) {
+    background::spawn_loop(&app, "focus timer", CHECK_INTERVAL, check);
+}
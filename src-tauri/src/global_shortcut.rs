@@ -0,0 +1,574 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::{main_window, settings};
+
+// Used until the user picks their own; an empty setting turns the shortcut off
+pub const DEFAULT_SHORTCUT: &str = "CmdOrCtrl+Shift+L";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Modifiers {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    // Cmd on macOS, the Windows/Super key elsewhere
+    meta: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Key {
+    // An uppercase letter or a digit
+    Char(char),
+    // F1 to F12
+    Function(u8),
+    Space,
+}
+
+// A key combination such as "CmdOrCtrl+Shift+L"
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut {
+    modifiers: Modifiers,
+    key: Key,
+}
+
+impl Shortcut {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = Modifiers {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            meta: false,
+        };
+        let mut key = None;
+
+        for token in text.split('+').map(str::trim) {
+            if key.is_some() {
+                return Err(format!("'{}': the key must come last", text));
+            }
+            match token.to_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" => {
+                    if cfg!(target_os = "macos") {
+                        modifiers.meta = true;
+                    } else {
+                        modifiers.ctrl = true;
+                    }
+                }
+                "cmd" | "command" | "super" | "meta" | "win" => modifiers.meta = true,
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" | "option" => modifiers.alt = true,
+                "space" => key = Some(Key::Space),
+                name => {
+                    let mut chars = name.chars();
+                    key = match (chars.next(), chars.next()) {
+                        (Some(c), None) if c.is_ascii_alphanumeric() => Some(Key::Char(c.to_ascii_uppercase())),
+                        (Some('f'), Some(_)) => match name[1..].parse() {
+                            Ok(n @ 1..=12) => Some(Key::Function(n)),
+                            _ => return Err(format!("'{}': unknown key '{}'", text, token)),
+                        },
+                        _ => return Err(format!("'{}': unknown key '{}'", text, token)),
+                    };
+                }
+            }
+        }
+
+        let key = key.ok_or_else(|| format!("'{}' has no key", text))?;
+        // Without Ctrl/Alt/Cmd the key would be swallowed from everything the
+        // user types; function keys are the exception
+        if !(modifiers.ctrl || modifiers.alt || modifiers.meta || matches!(key, Key::Function(_))) {
+            return Err(format!("'{}' needs Ctrl, Alt or Cmd/Super", text));
+        }
+        Ok(Shortcut { modifiers, key })
+    }
+}
+
+// The shortcut currently registered with the OS, if any
+#[derive(Default)]
+pub struct GlobalShortcut(Mutex<Option<Shortcut>>);
+
+// The persisted shortcut text; None when the user turned it off
+pub fn configured(app: &AppHandle) -> Option<String> {
+    match settings::get(app, settings::GLOBAL_SHORTCUT) {
+        Some(value) => value.as_str().filter(|text| !text.is_empty()).map(str::to_string),
+        None => Some(DEFAULT_SHORTCUT.to_string()),
+    }
+}
+
+// Registers the persisted shortcut at startup, or after settings are reset. A
+// combination another app already holds is logged rather than treated as fatal.
+pub fn init(app: &AppHandle) {
+    let Some(text) = configured(app) else {
+        if let Err(e) = swap(app, None) {
+            log::warn!("Failed to remove global shortcut: {}", e);
+        }
+        return;
+    };
+    match Shortcut::parse(&text).and_then(|shortcut| swap(app, Some(shortcut))) {
+        Ok(()) => log::info!("Registered global shortcut {}", text),
+        Err(e) => log::warn!("Global shortcut {} is unavailable: {}", text, e),
+    }
+}
+
+// Rebinds the shortcut (None turns it off) and persists the choice. If the new
+// combination can't be registered the previous one stays in place.
+pub fn set(app: &AppHandle, text: Option<&str>) -> Result<(), String> {
+    let text = text.map(str::trim).filter(|text| !text.is_empty());
+    let shortcut = text.map(Shortcut::parse).transpose()?;
+    swap(app, shortcut)?;
+    settings::set(app, settings::GLOBAL_SHORTCUT, text.unwrap_or(""))
+}
+
+fn swap(app: &AppHandle, shortcut: Option<Shortcut>) -> Result<(), String> {
+    let state = app.state::<GlobalShortcut>();
+    let mut current = state.0.lock().unwrap();
+    if *current == shortcut {
+        return Ok(());
+    }
+
+    if let Some(old) = *current {
+        platform::unregister(app, old);
+    }
+    if let Some(new) = shortcut {
+        if let Err(e) = platform::register(app, new) {
+            if let Some(old) = *current {
+                let _ = platform::register(app, old);
+            }
+            return Err(e);
+        }
+    }
+    *current = shortcut;
+    Ok(())
+}
+
+// RegisterHotKey delivers WM_HOTKEY to the thread that registered it, so a
+// dedicated thread owns the registration and pumps its message queue.
+// Requests are handed over through a channel, with WM_APP to wake it up.
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{Key, Shortcut};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Mutex, OnceLock};
+    use tauri::AppHandle;
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_APP, WM_HOTKEY, WM_USER,
+    };
+
+    const HOTKEY_ID: i32 = 1;
+
+    enum Request {
+        Register(Shortcut),
+        Unregister,
+    }
+
+    type Reply = Sender<Result<(), String>>;
+
+    struct Worker {
+        thread_id: u32,
+        requests: Mutex<Sender<(Request, Reply)>>,
+    }
+
+    static WORKER: OnceLock<Worker> = OnceLock::new();
+
+    fn virtual_key(key: Key) -> u32 {
+        match key {
+            // Letters and digits share their ASCII codes
+            Key::Char(c) => c as u32,
+            Key::Function(n) => 0x70 + n as u32 - 1,
+            Key::Space => 0x20,
+        }
+    }
+
+    fn start_worker(app: &AppHandle) -> Worker {
+        let (requests, receiver) = mpsc::channel::<(Request, Reply)>();
+        let (ready, thread_id) = mpsc::channel();
+        let app = app.clone();
+
+        std::thread::spawn(move || unsafe {
+            let mut msg: MSG = std::mem::zeroed();
+            // Make sure the thread has a message queue before anyone posts to it
+            PeekMessageW(&mut msg, std::ptr::null_mut(), WM_USER, WM_USER, PM_NOREMOVE);
+            let _ = ready.send(GetCurrentThreadId());
+
+            while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                match msg.message {
+                    WM_HOTKEY => super::toggle(&app),
+                    WM_APP => {
+                        for (request, reply) in receiver.try_iter() {
+                            let result = match request {
+                                Request::Register(shortcut) => {
+                                    let m = shortcut.modifiers;
+                                    let mut modifiers = MOD_NOREPEAT;
+                                    if m.ctrl {
+                                        modifiers |= MOD_CONTROL;
+                                    }
+                                    if m.shift {
+                                        modifiers |= MOD_SHIFT;
+                                    }
+                                    if m.alt {
+                                        modifiers |= MOD_ALT;
+                                    }
+                                    if m.meta {
+                                        modifiers |= MOD_WIN;
+                                    }
+                                    if RegisterHotKey(std::ptr::null_mut(), HOTKEY_ID, modifiers, virtual_key(shortcut.key)) == 0 {
+                                        Err("the combination is already in use by Windows or another app".to_string())
+                                    } else {
+                                        Ok(())
+                                    }
+                                }
+                                Request::Unregister => {
+                                    UnregisterHotKey(std::ptr::null_mut(), HOTKEY_ID);
+                                    Ok(())
+                                }
+                            };
+                            let _ = reply.send(result);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        Worker {
+            thread_id: thread_id.recv().unwrap_or(0),
+            requests: Mutex::new(requests),
+        }
+    }
+
+    fn call(app: &AppHandle, request: Request) -> Result<(), String> {
+        let worker = WORKER.get_or_init(|| start_worker(app));
+        let (reply, result) = mpsc::channel();
+        worker
+            .requests
+            .lock()
+            .unwrap()
+            .send((request, reply))
+            .map_err(|_| "Shortcut thread has stopped".to_string())?;
+        if unsafe { PostThreadMessageW(worker.thread_id, WM_APP, 0, 0) } == 0 {
+            return Err("Shortcut thread has stopped".to_string());
+        }
+        result.recv().map_err(|_| "Shortcut thread has stopped".to_string())?
+    }
+
+    pub fn register(app: &AppHandle, shortcut: Shortcut) -> Result<(), String> {
+        call(app, Request::Register(shortcut))
+    }
+
+    pub fn unregister(app: &AppHandle, _shortcut: Shortcut) {
+        let _ = call(app, Request::Unregister);
+    }
+}
+
+// Grabs the key on the X11 root window. Wayland has no global grabs, so this
+// only works in X11 sessions (or for XWayland-focused windows).
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{Key, Shortcut};
+    use std::sync::{Arc, OnceLock};
+    use std::time::{Duration, Instant};
+    use tauri::AppHandle;
+    use xcb::x;
+
+    // Held keys auto-repeat as fresh presses; ignore those within this window
+    const REPEAT_GUARD: Duration = Duration::from_millis(300);
+
+    struct Grabber {
+        conn: Arc<xcb::Connection>,
+        root: x::Window,
+    }
+
+    static GRABBER: OnceLock<Option<Grabber>> = OnceLock::new();
+
+    fn start_grabber(app: &AppHandle) -> Option<Grabber> {
+        let (conn, screen) = xcb::Connection::connect(None).ok()?;
+        let root = conn.get_setup().roots().nth(screen as usize)?.root();
+        let conn = Arc::new(conn);
+
+        let events = conn.clone();
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let mut last_press: Option<Instant> = None;
+            loop {
+                match events.wait_for_event() {
+                    Ok(xcb::Event::X(x::Event::KeyPress(_))) => {
+                        if last_press.is_none_or(|at| at.elapsed() > REPEAT_GUARD) {
+                            super::toggle(&app);
+                        }
+                        last_press = Some(Instant::now());
+                    }
+                    Ok(_) | Err(xcb::Error::Protocol(_)) => {}
+                    Err(xcb::Error::Connection(_)) => break,
+                }
+            }
+        });
+
+        Some(Grabber { conn, root })
+    }
+
+    fn keysym(key: Key) -> u32 {
+        match key {
+            Key::Char(c) => c.to_ascii_lowercase() as u32,
+            Key::Function(n) => 0xffbe + n as u32 - 1,
+            Key::Space => 0x20,
+        }
+    }
+
+    fn keycode(conn: &xcb::Connection, keysym: u32) -> Option<x::Keycode> {
+        let setup = conn.get_setup();
+        let (min, max) = (setup.min_keycode(), setup.max_keycode());
+        let cookie = conn.send_request(&x::GetKeyboardMapping {
+            first_keycode: min,
+            count: max - min + 1,
+        });
+        let mapping = conn.wait_for_reply(cookie).ok()?;
+        let per_keycode = mapping.keysyms_per_keycode() as usize;
+        let index = mapping.keysyms().iter().position(|sym| *sym == keysym)?;
+        Some(min + (index / per_keycode) as u8)
+    }
+
+    // The grab has to be repeated with Caps Lock and Num Lock on, or the
+    // shortcut stops working whenever either is active
+    fn masks(shortcut: Shortcut) -> [x::ModMask; 4] {
+        let m = shortcut.modifiers;
+        let mut base = x::ModMask::empty();
+        if m.ctrl {
+            base |= x::ModMask::CONTROL;
+        }
+        if m.shift {
+            base |= x::ModMask::SHIFT;
+        }
+        if m.alt {
+            base |= x::ModMask::N1;
+        }
+        if m.meta {
+            base |= x::ModMask::N4;
+        }
+        [
+            base,
+            base | x::ModMask::LOCK,
+            base | x::ModMask::N2,
+            base | x::ModMask::LOCK | x::ModMask::N2,
+        ]
+    }
+
+    fn ungrab(grabber: &Grabber, key: x::Keycode, masks: &[x::ModMask]) {
+        for &modifiers in masks {
+            grabber.conn.send_request(&x::UngrabKey {
+                key,
+                grab_window: grabber.root,
+                modifiers,
+            });
+        }
+        let _ = grabber.conn.flush();
+    }
+
+    pub fn register(app: &AppHandle, shortcut: Shortcut) -> Result<(), String> {
+        let grabber = GRABBER
+            .get_or_init(|| start_grabber(app))
+            .as_ref()
+            .ok_or_else(|| "global shortcuts need an X11 session".to_string())?;
+        let key = keycode(&grabber.conn, keysym(shortcut.key))
+            .ok_or_else(|| "the key isn't on the current keyboard layout".to_string())?;
+
+        let masks = masks(shortcut);
+        for (grabbed, &modifiers) in masks.iter().enumerate() {
+            let cookie = grabber.conn.send_request_checked(&x::GrabKey {
+                owner_events: false,
+                grab_window: grabber.root,
+                modifiers,
+                key,
+                pointer_mode: x::GrabMode::Async,
+                keyboard_mode: x::GrabMode::Async,
+            });
+            if grabber.conn.check_request(cookie).is_err() {
+                ungrab(grabber, key, &masks[..grabbed]);
+                return Err("the combination is already in use by another app".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unregister(_app: &AppHandle, shortcut: Shortcut) {
+        let Some(Some(grabber)) = GRABBER.get() else {
+            return;
+        };
+        if let Some(key) = keycode(&grabber.conn, keysym(shortcut.key)) {
+            ungrab(grabber, key, &masks(shortcut));
+        }
+    }
+}
+
+// Carbon hot keys are still the supported way to get system-wide shortcuts
+// without the Accessibility permission. They are delivered on the main run
+// loop, so registration happens on the main thread too.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Key, Shortcut};
+    use std::ffi::c_void;
+    use std::sync::{mpsc, Mutex, OnceLock};
+    use tauri::AppHandle;
+
+    type OSStatus = i32;
+    type EventHandlerProc = extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> OSStatus;
+
+    #[repr(C)]
+    struct EventTypeSpec {
+        event_class: u32,
+        event_kind: u32,
+    }
+
+    #[repr(C)]
+    struct EventHotKeyID {
+        signature: u32,
+        id: u32,
+    }
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn GetApplicationEventTarget() -> *mut c_void;
+        fn InstallEventHandler(
+            target: *mut c_void,
+            handler: EventHandlerProc,
+            num_types: u32,
+            types: *const EventTypeSpec,
+            user_data: *mut c_void,
+            out_ref: *mut *mut c_void,
+        ) -> OSStatus;
+        fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: *mut c_void,
+            options: u32,
+            out_ref: *mut *mut c_void,
+        ) -> OSStatus;
+        fn UnregisterEventHotKey(hot_key: *mut c_void) -> OSStatus;
+    }
+
+    const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+    const EVENT_HOT_KEY_PRESSED: u32 = 5;
+    const HOT_KEY_SIGNATURE: u32 = u32::from_be_bytes(*b"LFOS");
+    const EVENT_HOT_KEY_EXISTS_ERR: OSStatus = -9878;
+
+    const CMD_KEY: u32 = 0x0100;
+    const SHIFT_KEY: u32 = 0x0200;
+    const OPTION_KEY: u32 = 0x0800;
+    const CONTROL_KEY: u32 = 0x1000;
+
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    // The registered EventHotKeyRef, stored as an address so it can live in a static
+    static HOT_KEY: Mutex<usize> = Mutex::new(0);
+
+    extern "C" fn on_hot_key(_call: *mut c_void, _event: *mut c_void, _data: *mut c_void) -> OSStatus {
+        if let Some(app) = APP.get() {
+            super::toggle(app);
+        }
+        0
+    }
+
+    // Virtual key codes of the ANSI layout
+    fn key_code(key: Key) -> Option<u32> {
+        const LETTERS: [u32; 26] = [
+            0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F, 0x23, 0x0C,
+            0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+        ];
+        const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+        const FUNCTION: [u32; 12] = [0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F];
+        match key {
+            Key::Char(c @ 'A'..='Z') => Some(LETTERS[(c as u8 - b'A') as usize]),
+            Key::Char(c @ '0'..='9') => Some(DIGITS[(c as u8 - b'0') as usize]),
+            Key::Char(_) => None,
+            Key::Function(n) => FUNCTION.get(n as usize - 1).copied(),
+            Key::Space => Some(0x31),
+        }
+    }
+
+    fn on_main_thread<T: Send + 'static>(app: &AppHandle, f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+        // Setup already runs on the main thread; posting to it from there would deadlock
+        if unsafe { libc::pthread_main_np() } != 0 {
+            return Ok(f());
+        }
+        let (sender, receiver) = mpsc::channel();
+        app.run_on_main_thread(move || {
+            let _ = sender.send(f());
+        })
+        .map_err(|e| e.to_string())?;
+        receiver.recv().map_err(|e| e.to_string())
+    }
+
+    fn register_on_main_thread(key_code: u32, modifiers: u32) -> Result<(), String> {
+        static HANDLER: OnceLock<()> = OnceLock::new();
+        HANDLER.get_or_init(|| unsafe {
+            let spec = EventTypeSpec {
+                event_class: EVENT_CLASS_KEYBOARD,
+                event_kind: EVENT_HOT_KEY_PRESSED,
+            };
+            let mut handler = std::ptr::null_mut();
+            InstallEventHandler(GetApplicationEventTarget(), on_hot_key, 1, &spec, std::ptr::null_mut(), &mut handler);
+        });
+
+        let id = EventHotKeyID {
+            signature: HOT_KEY_SIGNATURE,
+            id: 1,
+        };
+        let mut hot_key = std::ptr::null_mut();
+        let status = unsafe { RegisterEventHotKey(key_code, modifiers, id, GetApplicationEventTarget(), 0, &mut hot_key) };
+        match status {
+            0 => {
+                *HOT_KEY.lock().unwrap() = hot_key as usize;
+                Ok(())
+            }
+            EVENT_HOT_KEY_EXISTS_ERR => Err("the combination is already in use by another app".to_string()),
+            status => Err(format!("RegisterEventHotKey failed ({})", status)),
+        }
+    }
+
+    pub fn register(app: &AppHandle, shortcut: Shortcut) -> Result<(), String> {
+        let _ = APP.set(app.clone());
+        let key_code = key_code(shortcut.key).ok_or_else(|| "unsupported key".to_string())?;
+        let m = shortcut.modifiers;
+        let mut modifiers = 0;
+        if m.meta {
+            modifiers |= CMD_KEY;
+        }
+        if m.shift {
+            modifiers |= SHIFT_KEY;
+        }
+        if m.alt {
+            modifiers |= OPTION_KEY;
+        }
+        if m.ctrl {
+            modifiers |= CONTROL_KEY;
+        }
+        on_main_thread(app, move || register_on_main_thread(key_code, modifiers))?
+    }
+
+    pub fn unregister(app: &AppHandle, _shortcut: Shortcut) {
+        let _ = on_main_thread(app, || {
+            let hot_key = std::mem::take(&mut *HOT_KEY.lock().unwrap());
+            if hot_key != 0 {
+                unsafe { UnregisterEventHotKey(hot_key as *mut c_void) };
+            }
+        });
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+mod platform {
+    use super::Shortcut;
+    use tauri::AppHandle;
+
+    pub fn register(_app: &AppHandle, _shortcut: Shortcut) -> Result<(), String> {
+        Err("global shortcuts aren't supported on this platform".to_string())
+    }
+
+    pub fn unregister(_app: &AppHandle, _shortcut: Shortcut) {}
+}
+
+fn toggle(app: &AppHandle) {
+    main_window::toggle(app);
+}
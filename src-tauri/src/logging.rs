@@ -0,0 +1,280 @@
+use chrono::Local;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::activity;
+
+// How many daily log files to keep
+const KEEP_LOG_FILES: usize = 7;
+// Lines logged before the log directory is known are held back, up to this many
+const MAX_PENDING_LINES: usize = 1000;
+// Backend output lines kept for a log view opened after they were printed
+const BACKEND_TAIL_LINES: usize = 500;
+// Backend output lines are cut short beyond this, so a runaway line (or output
+// with no newlines at all) can't grow memory without bound
+const MAX_BACKEND_LINE_BYTES: usize = 8 * 1024;
+const TRUNCATED_MARKER: &str = " …[truncated]";
+
+struct LogState {
+    dir: Option<PathBuf>,
+    day: String,
+    file: Option<File>,
+    pending: Vec<String>,
+}
+
+// Writes log lines to lifeos.YYYY-MM-DD.log in the app log dir, starting a new
+// file each day. Debug builds also echo to the console, since release builds
+// on Windows have none.
+struct DailyFileLogger {
+    state: Mutex<LogState>,
+}
+
+static LOGGER: DailyFileLogger = DailyFileLogger {
+    state: Mutex::new(LogState {
+        dir: None,
+        day: String::new(),
+        file: None,
+        pending: Vec::new(),
+    }),
+};
+
+fn prune_old_logs(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("lifeos.") && n.ends_with(".log"))
+        })
+        .collect();
+    // Dated names sort chronologically
+    logs.sort();
+    let excess = logs.len().saturating_sub(KEEP_LOG_FILES);
+    for old in &logs[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+}
+
+impl LogState {
+    fn write_line(&mut self, line: &str) {
+        let Some(dir) = self.dir.clone() else {
+            if self.pending.len() < MAX_PENDING_LINES {
+                self.pending.push(line.to_string());
+            }
+            return;
+        };
+
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        if self.file.is_none() || self.day != today {
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(format!("lifeos.{}.log", today)))
+                .ok();
+            self.day = today;
+            prune_old_logs(&dir);
+        }
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+impl Log for DailyFileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        if cfg!(debug_assertions) {
+            if record.level() <= Level::Warn {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+        }
+        self.state.lock().unwrap().write_line(&line);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.state.lock().unwrap().file.as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Installs the logger. Call first thing in main; lines are buffered until
+// set_log_dir is called.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        let level = if cfg!(debug_assertions) { LevelFilter::Debug } else { LevelFilter::Info };
+        log::set_max_level(level);
+    }
+}
+
+// Starts writing to files in `dir`, flushing anything logged so far
+pub fn set_log_dir(dir: PathBuf) -> std::io::Result<()> {
+    std::fs::create_dir_all(&dir)?;
+    let mut state = LOGGER.state.lock().unwrap();
+    state.dir = Some(dir);
+    for line in std::mem::take(&mut state.pending) {
+        state.write_line(&line);
+    }
+    Ok(())
+}
+
+// One line of backend output, as sent in python-log events
+#[derive(Clone, Serialize)]
+pub struct BackendLogLine {
+    // "stdout" or "stderr"
+    pub stream: &'static str,
+    pub line: String,
+    // Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+// The last BACKEND_TAIL_LINES lines of backend output, oldest first
+#[derive(Default)]
+pub struct BackendLogTail(Mutex<VecDeque<BackendLogLine>>);
+
+impl BackendLogTail {
+    pub fn lines(&self) -> Vec<BackendLogLine> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+// Reads backend output line by line, passing each line to `on_line`. Long
+// lines are truncated and the rest skipped up to the next newline. Invalid
+// UTF-8 is decoded lossily rather than ending the stream. Returns at EOF, on a
+// read error, or at the next line once `stop` is set (the process was stopped
+// but something it started still holds the pipe).
+pub fn read_backend_lines(output: impl Read, stop: &AtomicBool, mut on_line: impl FnMut(&str)) {
+    let mut reader = BufReader::new(output);
+    let mut line: Vec<u8> = Vec::new();
+    let mut truncated = false;
+    let mut emit = |line: &mut Vec<u8>, truncated: &mut bool| {
+        let mut text = String::from_utf8_lossy(line).trim_end_matches('\r').to_string();
+        if std::mem::take(truncated) {
+            text.push_str(TRUNCATED_MARKER);
+        }
+        line.clear();
+        on_line(&text);
+    };
+
+    loop {
+        let (consumed, complete) = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => {
+                let end = chunk.iter().position(|&b| b == b'\n');
+                let part = &chunk[..end.unwrap_or(chunk.len())];
+                let room = MAX_BACKEND_LINE_BYTES - line.len();
+                if part.len() > room {
+                    truncated = true;
+                }
+                line.extend_from_slice(&part[..part.len().min(room)]);
+                (part.len() + usize::from(end.is_some()), end.is_some())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        reader.consume(consumed);
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        if complete {
+            emit(&mut line, &mut truncated);
+        }
+    }
+    if !line.is_empty() || truncated {
+        emit(&mut line, &mut truncated);
+    }
+}
+
+// Logs a line of backend output to the file, keeps it in the tail and sends it
+// to the UI as a python-log event
+pub fn forward_backend_line(app: &AppHandle, stream: &'static str, line: &str) {
+    if stream == "stderr" {
+        log::info!(target: "python", "[stderr] {}", line);
+    } else {
+        log::info!(target: "python", "{}", line);
+    }
+
+    let entry = BackendLogLine {
+        stream,
+        line: line.to_string(),
+        ts: activity::now_ms(),
+    };
+    {
+        let mut tail = app.state::<BackendLogTail>().inner().0.lock().unwrap();
+        if tail.len() == BACKEND_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(entry.clone());
+    }
+    let _ = app.emit("python-log", entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read_all(output: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        read_backend_lines(Cursor::new(output), &AtomicBool::new(false), |line| lines.push(line.to_string()));
+        lines
+    }
+
+    #[test]
+    fn truncates_a_stream_without_newlines() {
+        let lines = read_all(&vec![b'x'; 100 * 1024]);
+        assert_eq!(lines.len(), 1);
+        let text = lines[0].strip_suffix(TRUNCATED_MARKER).expect("missing truncation marker");
+        assert_eq!(text.len(), MAX_BACKEND_LINE_BYTES);
+    }
+
+    #[test]
+    fn keeps_reading_after_a_truncated_line() {
+        let mut output = vec![b'x'; 20 * 1024];
+        output.extend_from_slice(b"\nINFO: started\r\n");
+        let lines = read_all(&output);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(TRUNCATED_MARKER));
+        assert_eq!(lines[1], "INFO: started");
+    }
+
+    #[test]
+    fn decodes_invalid_utf8_lossily() {
+        let lines = read_all(b"caf\xe9 ok\n\xff\xfe\nlast");
+        assert_eq!(lines, ["caf\u{FFFD} ok", "\u{FFFD}\u{FFFD}", "last"]);
+    }
+
+    #[test]
+    fn stops_at_the_next_line_once_asked() {
+        let mut lines = Vec::new();
+        read_backend_lines(Cursor::new(b"one\ntwo\n"), &AtomicBool::new(true), |line| lines.push(line.to_string()));
+        assert!(lines.is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, LogicalSize, Manager, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow, Window};
+
+use crate::settings;
+
+// Shows the main window. Whether it also takes keyboard focus is controlled by
+// the steal_focus_on_show setting, so a passive nudge doesn't interrupt typing.
+pub fn show<R: Runtime>(app: &AppHandle<R>) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+
+    if settings::get_bool(app, settings::STEAL_FOCUS_ON_SHOW, true) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    } else {
+        // Non-focusable windows are shown without being activated; make it
+        // focusable again afterwards so the user can still click into it
+        let _ = window.set_focusable(false);
+        let _ = window.show();
+        let _ = window.set_focusable(true);
+    }
+}
+
+// Hides the main window if it is on screen, otherwise brings it back through
+// show, e.g. for the global shortcut
+pub fn toggle<R: Runtime>(app: &AppHandle<R>) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        let _ = window.unminimize();
+        show(app);
+    }
+}
+
+// Matches the main window's size in tauri.conf.json
+const DEFAULT_SIZE: LogicalSize<f64> = LogicalSize::new(1200.0, 800.0);
+
+// How much of the title bar must land on a monitor for a restored position to
+// be kept; anything less is treated as off-screen
+const MIN_VISIBLE_WIDTH: i32 = 100;
+const MIN_VISIBLE_HEIGHT: i32 = 40;
+
+// Last normal (un-maximized) geometry of the main window, in physical pixels
+#[derive(Serialize, Deserialize)]
+struct WindowState {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+}
+
+fn saved_state<R: Runtime>(app: &AppHandle<R>) -> Option<WindowState> {
+    serde_json::from_value(settings::get(app, settings::MAIN_WINDOW_STATE)?).ok()
+}
+
+// Records the window's geometry. Called on every move and resize, so the write
+// is left to the store's debounced auto-save unless `flush` is set.
+pub fn save_state<R: Runtime>(window: &Window<R>, flush: bool) {
+    if window.is_minimized().unwrap_or(false) {
+        // Minimized windows report a bogus off-screen position on Windows
+        return;
+    }
+    let app = window.app_handle();
+    let maximized = window.is_maximized().unwrap_or(false);
+
+    let state = if maximized {
+        // Keep the normal geometry so un-maximizing after a restart goes back there
+        match saved_state(app) {
+            Some(previous) => WindowState { maximized, ..previous },
+            None => return,
+        }
+    } else {
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+            return;
+        };
+        WindowState {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized,
+        }
+    };
+
+    let Ok(value) = serde_json::to_value(&state) else {
+        return;
+    };
+    if flush {
+        if let Err(e) = settings::set(app, settings::MAIN_WINDOW_STATE, value) {
+            log::warn!("Failed to save window state: {}", e);
+        }
+    } else {
+        settings::set_deferred(app, settings::MAIN_WINDOW_STATE, value);
+    }
+}
+
+// Whether enough of a window at this geometry would be on some connected monitor
+fn on_screen<R: Runtime>(window: &WebviewWindow<R>, state: &WindowState) -> bool {
+    let monitors = window.available_monitors().unwrap_or_default();
+    monitors.iter().any(|monitor| {
+        let origin = monitor.position();
+        let size = monitor.size();
+        let right = (state.x + state.width as i32).min(origin.x + size.width as i32);
+        let bottom = (state.y + MIN_VISIBLE_HEIGHT).min(origin.y + size.height as i32);
+        right - state.x.max(origin.x) >= MIN_VISIBLE_WIDTH && bottom - state.y.max(origin.y) >= MIN_VISIBLE_HEIGHT
+    })
+}
+
+// Puts the main window back where the user left it. Geometry saved on a display
+// that is no longer connected is dropped in favour of a centred window on the
+// primary monitor, sized to fit it.
+pub fn restore_state<R: Runtime>(window: &WebviewWindow<R>) {
+    let Some(state) = saved_state(window.app_handle()) else {
+        return;
+    };
+
+    if on_screen(window, &state) {
+        let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+        let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+    } else {
+        log::info!("Saved window position is off-screen, centring instead");
+        let monitor = window.primary_monitor().ok().flatten();
+        let size = match monitor {
+            Some(monitor) => PhysicalSize::new(
+                state.width.min(monitor.size().width),
+                state.height.min(monitor.size().height),
+            ),
+            None => PhysicalSize::new(state.width, state.height),
+        };
+        let _ = window.set_size(size);
+        let _ = window.center();
+    }
+
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+// Forgets the saved geometry and puts the window back at its default size, centred
+pub fn reset_state<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    settings::delete(app, settings::MAIN_WINDOW_STATE)?;
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    let _ = window.unmaximize();
+    window.set_size(DEFAULT_SIZE).map_err(|e| e.to_string())?;
+    window.center().map_err(|e| e.to_string())?;
+    show(app);
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::{activity, activity_queue, background, settings, tracking};
+
+// Longest a timed manual activity may run
+pub const MAX_DURATION_SECS: u64 = 24 * 60 * 60;
+// How often the timer checks whether a timed activity is over
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Serializes read-modify-write of the stored activity between commands and the timer
+static MANUAL_LOCK: Mutex<()> = Mutex::new(());
+
+// Something the user is doing off screen, such as a meeting or reading. Stored
+// in settings so an open activity survives a restart.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManualActivity {
+    pub label: String,
+    pub category: String,
+    pub started_at_ms: u64,
+    // When a timed activity ends by itself; None runs until the next one or end_manual_activity
+    pub ends_at_ms: Option<u64>,
+}
+
+impl ManualActivity {
+    // The finished segment record, in the same shape as the tracking loop's
+    fn into_record(self, ended_at_ms: u64, reason: &str) -> serde_json::Value {
+        let ended_at_ms = ended_at_ms.max(self.started_at_ms);
+        let time = |ms: u64| Local.timestamp_millis_opt(ms as i64).single().unwrap_or_else(Local::now);
+        let mut record = serde_json::json!({
+            "app_name": self.label,
+            "window_title": "",
+            "category": self.category,
+            "source": "manual",
+            "start": time(self.started_at_ms).to_rfc3339(),
+            "end": time(ended_at_ms).to_rfc3339(),
+            "duration_secs": (ended_at_ms - self.started_at_ms) as f64 / 1000.0,
+            "end_reason": reason
+        });
+        tracking::stamp(&mut record);
+        record
+    }
+}
+
+fn load<R: Runtime>(app: &AppHandle<R>) -> Option<ManualActivity> {
+    settings::get(app, settings::MANUAL_ACTIVITY).and_then(|activity| serde_json::from_value(activity).ok())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, activity: Option<&ManualActivity>) -> Result<(), String> {
+    match activity {
+        Some(activity) => {
+            let activity = serde_json::to_value(activity).map_err(|e| e.to_string())?;
+            settings::set(app, settings::MANUAL_ACTIVITY, activity)
+        }
+        None => settings::delete(app, settings::MANUAL_ACTIVITY),
+    }
+}
+
+pub fn current<R: Runtime>(app: &AppHandle<R>) -> Option<ManualActivity> {
+    load(app)
+}
+
+// Whether automatic tracking stands down while a manual activity is open,
+// rather than carrying on with its records marked
+pub fn suppresses_tracking<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_bool(app, settings::MANUAL_SUPPRESSES_TRACKING, true)
+}
+
+// The open activity's label and when it started, for the tracking loop
+pub fn open_since<R: Runtime>(app: &AppHandle<R>) -> Option<(String, Instant)> {
+    let activity = load(app)?;
+    let elapsed = Duration::from_millis(activity::now_ms().saturating_sub(activity.started_at_ms));
+    let now = Instant::now();
+    Some((activity.label, now.checked_sub(elapsed).unwrap_or(now)))
+}
+
+// Counts and sends a finished manual activity
+async fn send<R: Runtime>(app: &AppHandle<R>, record: serde_json::Value) {
+    tracking::tally(app, &record);
+    activity_queue::post(app, record).await;
+}
+
+// Starts a manual activity, ending any open one first. With `duration_secs`
+// it ends by itself after that long.
+pub async fn start<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    category: &str,
+    duration_secs: Option<u64>,
+) -> Result<ManualActivity, String> {
+    let (label, category) = (label.trim(), category.trim());
+    if label.is_empty() || category.is_empty() {
+        return Err("A manual activity needs a label and a category".to_string());
+    }
+    if duration_secs.is_some_and(|secs| secs == 0 || secs > MAX_DURATION_SECS) {
+        return Err(format!("Manual activities can last 1-{} seconds", MAX_DURATION_SECS));
+    }
+
+    let now = activity::now_ms();
+    let activity = ManualActivity {
+        label: label.to_string(),
+        category: category.to_string(),
+        started_at_ms: now,
+        ends_at_ms: duration_secs.map(|secs| now + secs * 1000),
+    };
+    let previous = {
+        let _guard = MANUAL_LOCK.lock().unwrap();
+        let previous = load(app);
+        save(app, Some(&activity))?;
+        previous
+    };
+    if let Some(previous) = previous {
+        send(app, previous.into_record(now, "replaced")).await;
+    }
+    log::info!("Manual activity '{}' started", activity.label);
+    let _ = app.emit("manual-activity-changed", &activity);
+    Ok(activity)
+}
+
+// Ends the open manual activity now and sends it
+pub async fn end<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let activity = {
+        let _guard = MANUAL_LOCK.lock().unwrap();
+        let activity = load(app).ok_or("No manual activity in progress")?;
+        save(app, None)?;
+        activity
+    };
+    log::info!("Manual activity '{}' ended", activity.label);
+    send(app, activity.into_record(activity::now_ms(), "ended")).await;
+    let _ = app.emit("manual-activity-changed", serde_json::Value::Null);
+    Ok(())
+}
+
+// Ends a timed activity once its time is up. One that ran out while the app
+// was closed is recorded up to its end time.
+fn check<R: Runtime>(app: &AppHandle<R>) {
+    let activity = {
+        let _guard = MANUAL_LOCK.lock().unwrap();
+        let Some(activity) = load(app) else {
+            return;
+        };
+        match activity.ends_at_ms {
+            Some(end) if end <= activity::now_ms() => {}
+            _ => return,
+        }
+        if let Err(e) = save(app, None) {
+            log::warn!("Failed to clear finished manual activity: {}", e);
+            return;
+        }
+        activity
+    };
+    let end = activity.ends_at_ms.unwrap_or_default();
+    log::info!("Manual activity '{}' finished", activity.label);
+    tauri::async_runtime::block_on(send(app, activity.into_record(end, "timer")));
+    let _ = app.emit("manual-activity-changed", serde_json::Value::Null);
+}
+
+// Runs the timer that ends timed manual activities, independent of the frontend
+pub fn start_timer<R: Runtime>(app: AppHandle<R>) {
+    background::spawn_loop(&app, "manual activity timer", CHECK_INTERVAL, check);
+}
@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{backend, background, os_integration};
+
+// How often internet reachability is probed
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Assume online until the first probe says otherwise
+static ONLINE: AtomicBool = AtomicBool::new(true);
+
+// The last probed internet connectivity; cheap enough to call anywhere
+pub fn is_online() -> bool {
+    ONLINE.load(Ordering::Relaxed)
+}
+
+// Tells the backend so it can skip LLM calls while offline. The backend is on
+// 127.0.0.1, so this goes through regardless of internet connectivity.
+fn notify_backend(app: &AppHandle, online: bool) {
+    let client = app.state::<reqwest::Client>();
+    let request = client
+        .post(backend::url("/api/network-status"))
+        .json(&serde_json::json!({ "online": online }))
+        .timeout(Duration::from_secs(2))
+        .send();
+    if let Err(e) = tauri::async_runtime::block_on(request) {
+        log::debug!("Could not pass network status to the backend: {}", e);
+    }
+}
+
+// Probes connectivity in the background and emits network-status when it
+// changes. Only internet-bound work reacts to this; activity posts to the
+// local backend carry on (and queue) as usual.
+pub fn start_monitor(app: AppHandle) {
+    background::spawn_loop(&app, "network monitor", CHECK_INTERVAL, |app| {
+        let online = os_integration::is_online();
+        if ONLINE.swap(online, Ordering::SeqCst) != online {
+            log::info!("Network is {}", if online { "back online" } else { "offline" });
+            let _ = app.emit("network-status", serde_json::json!({ "online": online }));
+            notify_backend(app, online);
+        }
+    });
+}
@@ -1,42 +1,2098 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{Duration, Instant};
 
-pub fn get_browser_url(app_name: &str) -> Option<String> {
-    let browser_script_name = if app_name.contains("Chrome") {
-        "Google Chrome"
-    } else if app_name.contains("Arc") {
-        "Arc"
-    } else if app_name.contains("Brave") {
-        "Brave Browser"
-    } else if app_name.contains("Safari") {
-        "Safari"
+// A URL read from the focused browser window. URLs from windows known to be
+// private/incognito are never returned.
+pub struct BrowserUrl {
+    pub url: String,
+    // False when the browser couldn't tell us whether the window is private
+    pub privacy_known: bool,
+}
+
+// Firefox has no AppleScript dictionary, so walk its accessibility tree for the
+// web area and read its AXURL. Needs the Accessibility permission; without it
+// osascript fails and this returns None. Private windows are recognised by
+// their title and skipped. The focused window's title is passed as the first
+// argument.
+#[cfg(target_os = "macos")]
+const FIREFOX_URL_SCRIPT: &str = r#"
+const proc = Application('System Events').processes.byName('Firefox');
+function find(el, depth) {
+    if (depth > 12) return null;
+    let children;
+    try { children = el.uiElements(); } catch (e) { return null; }
+    for (const child of children) {
+        let role = '';
+        try { role = child.role(); } catch (e) {}
+        if (role === 'AXWebArea') {
+            try { return String(child.attributes.byName('AXURL').value()); } catch (e) { return null; }
+        }
+        const found = find(child, depth + 1);
+        if (found) return found;
+    }
+    return null;
+}
+function run(argv) {
+    const windows = proc.windows();
+    if (windows.length === 0) return '';
+    const wanted = argv[0] || '';
+    const win = windows.find(w => wanted !== '' && String(w.name()) === wanted) || windows[0];
+    if (String(win.name()).includes('Private Browsing')) return 'incognito\t';
+    const url = find(win, 0);
+    return url ? 'normal\t' + url : '';
+}
+"#;
+
+// The scripts below take the focused window's title as their first argument and
+// read the browser window with that title. `front window` is only a fallback: with
+// several windows, or the browser on another Space, it isn't always the one
+// that has focus.
+
+// Chromium browsers report `mode` ("normal"/"incognito") per window; browsers
+// without it report "unknown"
+#[cfg(target_os = "macos")]
+fn chromium_url_script(app: &str) -> String {
+    format!(
+        r#"
+        on run argv
+            set wanted to item 1 of argv
+            tell application "{}"
+                if (count of windows) = 0 then return ""
+                set target to front window
+                if wanted is not "" then
+                    repeat with w in windows
+                        if (name of w) is wanted then
+                            set target to contents of w
+                            exit repeat
+                        end if
+                    end repeat
+                end if
+                set privacy to "unknown"
+                try
+                    set privacy to (mode of target) as text
+                end try
+                return privacy & tab & (URL of active tab of target)
+            end tell
+        end run
+        "#,
+        app
+    )
+}
+
+// Safari has no scripting property for private windows
+#[cfg(target_os = "macos")]
+const SAFARI_URL_SCRIPT: &str = r#"
+on run argv
+    set wanted to item 1 of argv
+    tell application "Safari"
+        if (count of windows) = 0 then return ""
+        set target to front window
+        if wanted is not "" then
+            repeat with w in windows
+                if (name of w) is wanted then
+                    set target to contents of w
+                    exit repeat
+                end if
+            end repeat
+        end if
+        return "unknown" & tab & (URL of current tab of target)
+    end tell
+end run
+"#;
+
+// How the URL is read from a macOS browser
+#[cfg(target_os = "macos")]
+enum MacBrowser {
+    // AppleScript application name of a Chromium-based browser
+    Chromium(&'static str),
+    Safari,
+    Firefox,
+}
+
+// App-name words mapped to browsers. The first match wins, so more specific
+// names go before their prefixes ("Opera GX" before "Opera").
+#[cfg(target_os = "macos")]
+const MAC_BROWSERS: [(&str, MacBrowser); 9] = [
+    ("Firefox", MacBrowser::Firefox),
+    ("Chrome", MacBrowser::Chromium("Google Chrome")),
+    ("Arc", MacBrowser::Chromium("Arc")),
+    ("Brave", MacBrowser::Chromium("Brave Browser")),
+    ("Microsoft Edge", MacBrowser::Chromium("Microsoft Edge")),
+    ("Vivaldi", MacBrowser::Chromium("Vivaldi")),
+    ("Opera GX", MacBrowser::Chromium("Opera GX")),
+    ("Opera", MacBrowser::Chromium("Opera")),
+    ("Safari", MacBrowser::Safari),
+];
+
+// Whether `needle` appears in `haystack` as whole words, so "Arc" doesn't
+// match "Archive Utility"
+#[cfg(target_os = "macos")]
+fn contains_words(haystack: &str, needle: &str) -> bool {
+    haystack.match_indices(needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn mac_browser(app_name: &str) -> Option<&'static MacBrowser> {
+    MAC_BROWSERS
+        .iter()
+        .find(|(name, _)| contains_words(app_name, name))
+        .map(|(_, browser)| browser)
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_browser_url(app_name: &str, window_title: &str) -> Option<BrowserUrl> {
+    let (language, script) = match mac_browser(app_name)? {
+        MacBrowser::Firefox => ("JavaScript", FIREFOX_URL_SCRIPT.to_string()),
+        MacBrowser::Chromium(app) => ("AppleScript", chromium_url_script(app)),
+        MacBrowser::Safari => ("AppleScript", SAFARI_URL_SCRIPT.to_string()),
+    };
+
+    let mut command = Command::new("osascript");
+    command
+        .args(["-l", language, "-e", &script, window_title])
+        // Force UTF-8 output regardless of the user's locale settings
+        .env("LANG", "en_US.UTF-8")
+        .env("LC_ALL", "en_US.UTF-8");
+    let stdout = match output_within(command, BROWSER_URL_TIMEOUT) {
+        Ok(stdout) => stdout?,
+        Err(e) => {
+            log::warn!("Reading the URL from {} {}", app_name, e);
+            return None;
+        }
+    };
+
+    // "<mode>\t<url>"
+    let text = decode_output(&stdout);
+    let (mode, url) = text.split_once('\t')?;
+    let url = url.trim();
+    // A blank tab has no URL; AppleScript reports that as "missing value"
+    if mode == "incognito" || url.is_empty() || url == "missing value" {
+        return None;
+    }
+    Some(BrowserUrl {
+        url: normalize_url(url),
+        privacy_known: mode == "normal",
+    })
+}
+
+// Reads the address bar of Chromium-based browsers through UI Automation.
+// Returns None if the browser isn't supported or the address bar can't be found.
+// Private windows can't be told apart here, so URLs are reported with
+// privacy_known = false.
+#[cfg(target_os = "windows")]
+pub fn get_browser_url(app_name: &str, _window_title: &str) -> Option<BrowserUrl> {
+    use windows::Win32::System::Variant::VARIANT;
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED};
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationValuePattern, TreeScope_Descendants,
+        UIA_ControlTypePropertyId, UIA_EditControlTypeId, UIA_ValuePatternId,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    if !["Chrome", "Edge", "Brave", "Vivaldi", "Opera"].iter().any(|b| app_name.contains(b)) {
+        return None;
+    }
+
+    let value = unsafe {
+        // The tracking task calls this from whichever tokio worker it is on, so each
+        // worker joins the MTA on first use and stays in it until the runtime exits.
+        // S_FALSE (already joined) is fine; a worker already in an STA still has COM,
+        // so any failure is left for CoCreateInstance to report.
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let window = automation.ElementFromHandle(GetForegroundWindow()).ok()?;
+
+        // The address bar is the first edit control in the browser's tree
+        let is_edit = automation
+            .CreatePropertyCondition(UIA_ControlTypePropertyId, &VARIANT::from(UIA_EditControlTypeId.0))
+            .ok()?;
+        let address_bar = window.FindFirst(TreeScope_Descendants, &is_edit).ok()?;
+        let pattern: IUIAutomationValuePattern = address_bar.GetCurrentPatternAs(UIA_ValuePatternId).ok()?;
+        pattern.CurrentValue().ok()?.to_string()
+    };
+
+    Some(BrowserUrl {
+        url: address_bar_url(&value)?,
+        privacy_known: false,
+    })
+}
+
+// Turns address bar text into a URL. The address bar hides the scheme for http(s) pages.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn address_bar_url(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(if value.contains("://") {
+        normalize_url(value)
     } else {
+        normalize_url(&format!("https://{}", value))
+    })
+}
+
+// An object on the AT-SPI accessibility bus: (bus name, object path)
+#[cfg(target_os = "linux")]
+type AtspiNode = (String, zbus::zvariant::OwnedObjectPath);
+
+#[cfg(target_os = "linux")]
+const ATSPI_ACCESSIBLE: &str = "org.a11y.atspi.Accessible";
+#[cfg(target_os = "linux")]
+const ATSPI_ROLE_ENTRY: u32 = 79;
+#[cfg(target_os = "linux")]
+const ATSPI_ROLE_DOCUMENT_FRAME: u32 = 82;
+#[cfg(target_os = "linux")]
+const ATSPI_ROLE_DOCUMENT_WEB: u32 = 95;
+#[cfg(target_os = "linux")]
+const ATSPI_STATE_ACTIVE: u32 = 1;
+// Upper bound on nodes visited per lookup, so a huge UI tree can't stall the loop
+#[cfg(target_os = "linux")]
+const ATSPI_MAX_NODES: usize = 500;
+
+// Connection to the accessibility bus, whose address is published on the session bus.
+// Kept once established; retried on the next call while it isn't.
+#[cfg(target_os = "linux")]
+fn atspi_connection() -> Option<zbus::blocking::Connection> {
+    static CONNECTION: std::sync::Mutex<Option<zbus::blocking::Connection>> = std::sync::Mutex::new(None);
+    let mut connection = CONNECTION.lock().unwrap();
+    if connection.is_none() {
+        let session = zbus::blocking::Connection::session().ok()?;
+        let reply = session
+            .call_method(Some("org.a11y.Bus"), "/org/a11y/bus", Some("org.a11y.Bus"), "GetAddress", &())
+            .ok()?;
+        let address: String = reply.body().deserialize().ok()?;
+        *connection = Some(zbus::blocking::connection::Builder::address(address.as_str()).ok()?.build().ok()?);
+    }
+    connection.clone()
+}
+
+#[cfg(target_os = "linux")]
+fn atspi_call<B, T>(conn: &zbus::blocking::Connection, node: &AtspiNode, interface: &str, method: &str, body: &B) -> Option<T>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+    T: serde::de::DeserializeOwned + zbus::zvariant::Type,
+{
+    let reply = conn
+        .call_method(Some(node.0.as_str()), node.1.as_str(), Some(interface), method, body)
+        .ok()?;
+    reply.body().deserialize().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn atspi_children(conn: &zbus::blocking::Connection, node: &AtspiNode) -> Vec<AtspiNode> {
+    atspi_call(conn, node, ATSPI_ACCESSIBLE, "GetChildren", &()).unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn atspi_name(conn: &zbus::blocking::Connection, node: &AtspiNode) -> Option<String> {
+    let value: zbus::zvariant::OwnedValue =
+        atspi_call(conn, node, "org.freedesktop.DBus.Properties", "Get", &(ATSPI_ACCESSIBLE, "Name"))?;
+    String::try_from(value).ok()
+}
+
+// First text entry in the window, which is the address bar in Firefox and
+// Chromium. Web content is skipped so form fields on the page can't match.
+#[cfg(target_os = "linux")]
+fn atspi_find_address_bar(conn: &zbus::blocking::Connection, frame: &AtspiNode) -> Option<AtspiNode> {
+    let mut stack = vec![frame.clone()];
+    let mut visited = 0;
+    while let Some(node) = stack.pop() {
+        visited += 1;
+        if visited > ATSPI_MAX_NODES {
+            return None;
+        }
+        match atspi_call::<_, u32>(conn, &node, ATSPI_ACCESSIBLE, "GetRole", &()) {
+            Some(ATSPI_ROLE_ENTRY) => return Some(node),
+            Some(ATSPI_ROLE_DOCUMENT_FRAME | ATSPI_ROLE_DOCUMENT_WEB) => continue,
+            _ => {}
+        }
+        // Reversed so the tree is walked in document order
+        stack.extend(atspi_children(conn, &node).into_iter().rev());
+    }
+    None
+}
+
+// Reads the address bar of the active browser window over the AT-SPI
+// accessibility bus. Works on X11 and Wayland alike, but only when desktop
+// accessibility is enabled (GNOME/KDE turn it on with a screen reader or
+// toolkit-accessibility); otherwise browsers don't expose their UI and this
+// returns None. Firefox private windows are recognised by their title;
+// Chromium doesn't mark incognito windows, so its URLs are privacy-unknown.
+#[cfg(target_os = "linux")]
+pub fn get_browser_url(app_name: &str, _window_title: &str) -> Option<BrowserUrl> {
+    let lower = app_name.to_lowercase();
+    let browser = ["firefox", "chrom", "brave", "edge", "vivaldi", "opera"]
+        .into_iter()
+        .find(|b| lower.contains(b))?;
+
+    let conn = atspi_connection()?;
+    let registry: AtspiNode = (
+        "org.a11y.atspi.Registry".to_string(),
+        zbus::zvariant::OwnedObjectPath::try_from("/org/a11y/atspi/accessible/root").ok()?,
+    );
+    let app = atspi_children(&conn, &registry)
+        .into_iter()
+        .find(|node| atspi_name(&conn, node).is_some_and(|name| name.to_lowercase().contains(browser)))?;
+    let frame = atspi_children(&conn, &app).into_iter().find(|node| {
+        atspi_call::<_, Vec<u32>>(&conn, node, ATSPI_ACCESSIBLE, "GetState", &())
+            .is_some_and(|states| states.first().is_some_and(|bits| bits & (1 << ATSPI_STATE_ACTIVE) != 0))
+    })?;
+
+    let firefox = browser == "firefox";
+    if firefox && atspi_name(&conn, &frame).is_some_and(|title| title.contains("Private Browsing")) {
         return None;
+    }
+
+    let address_bar = atspi_find_address_bar(&conn, &frame)?;
+    let text: String = atspi_call(&conn, &address_bar, "org.a11y.atspi.Text", "GetText", &(0i32, -1i32))?;
+    Some(BrowserUrl {
+        url: address_bar_url(&text)?,
+        privacy_known: firefox,
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn get_browser_url(_app_name: &str, _window_title: &str) -> Option<BrowserUrl> {
+    None
+}
+
+// Opens a folder in Finder / Explorer / the default file manager. The path is
+// passed as a plain argument, never through a shell, and must be absolute so
+// it can't be taken for an option.
+pub fn open_in_file_manager(path: &Path) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err(format!("Refusing to open relative path {}", path.display()));
+    }
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(target_os = "windows")]
+    let opener = "explorer";
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let opener = "xdg-open";
+
+    Command::new(opener)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+// The System Settings pane where the user grants Screen Recording, which macOS
+// requires before other apps' window titles can be read
+pub const SCREEN_RECORDING_SETTINGS_URL: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture";
+
+// Opens the Screen Recording privacy pane
+#[cfg(target_os = "macos")]
+pub fn open_screen_recording_settings() -> Result<(), String> {
+    Command::new("open")
+        .arg(SCREEN_RECORDING_SETTINGS_URL)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open System Settings: {}", e))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn open_screen_recording_settings() -> Result<(), String> {
+    Err("Screen Recording permission only exists on macOS".to_string())
+}
+
+// OS permissions tracking depends on. None where the OS has no such permission.
+#[derive(Serialize)]
+pub struct Permissions {
+    pub accessibility: Option<bool>,
+    pub screen_recording: Option<bool>,
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> u8;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> u8;
+}
+
+// Checks without prompting the user
+#[cfg(target_os = "macos")]
+pub fn permissions() -> Permissions {
+    Permissions {
+        accessibility: Some(unsafe { AXIsProcessTrusted() } != 0),
+        screen_recording: Some(unsafe { CGPreflightScreenCaptureAccess() } != 0),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn permissions() -> Permissions {
+    Permissions {
+        accessibility: None,
+        screen_recording: None,
+    }
+}
+
+// A hung browser (or one showing a modal dialog) can keep osascript waiting
+// for many seconds, which would stall the tracking loop
+#[cfg(target_os = "macos")]
+const BROWSER_URL_TIMEOUT: Duration = Duration::from_millis(800);
+
+// Runs `command` and returns its stdout, or None if it failed. Err means it
+// didn't finish within `timeout` and was killed.
+#[cfg(target_os = "macos")]
+fn output_within(mut command: Command, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let deadline = Instant::now() + timeout;
+    let Ok(mut child) = command.stdout(Stdio::piped()).stderr(Stdio::null()).spawn() else {
+        return Ok(None);
+    };
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}ms", timeout.as_millis()));
+            }
+            Err(_) => return Ok(None),
+        }
     };
+    if !status.success() {
+        return Ok(None);
+    }
+    let mut stdout = Vec::new();
+    if let Some(mut pipe) = child.stdout.take() {
+        if pipe.read_to_end(&mut stdout).is_err() {
+            return Ok(None);
+        }
+    }
+    Ok(Some(stdout))
+}
+
+// Decodes command output as UTF-8, only falling back to lossy decoding if the
+// bytes are genuinely invalid
+#[cfg(any(target_os = "macos", test))]
+fn decode_output(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.trim().to_string(),
+        Err(e) => {
+            log::warn!("osascript returned invalid UTF-8 ({}), decoding lossily", e);
+            String::from_utf8_lossy(bytes).trim().to_string()
+        }
+    }
+}
+
+// Normalizes a captured URL so the same page always produces the same string:
+// internationalized hosts become punycode and non-ASCII paths are percent-encoded,
+// e.g. `https://例え.jp/ページ` -> `https://xn--r8jz45g.jp/%E3%83%9A%E3%83%BC%E3%82%B8`.
+// Strings that don't parse as URLs (e.g. `about:blank` variants) are returned as-is.
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub fn normalize_url(raw: &str) -> String {
+    match url::Url::parse(raw) {
+        Ok(parsed) => parsed.to_string(),
+        Err(_) => raw.to_string(),
+    }
+}
+
+// Returns the system clipboard's change counter. Only the counter is read,
+// never the clipboard contents.
+#[cfg(target_os = "macos")]
+fn clipboard_change_count() -> Option<u64> {
+    let output = Command::new("osascript")
+        .args([
+            "-l",
+            "JavaScript",
+            "-e",
+            "ObjC.import('AppKit'); $.NSPasteboard.generalPasteboard.changeCount",
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(target_os = "windows")]
+fn clipboard_change_count() -> Option<u64> {
+    // 0 means the sequence number is unavailable (e.g. no window station access)
+    let seq = unsafe { windows_sys::Win32::System::DataExchange::GetClipboardSequenceNumber() };
+    if seq == 0 {
+        None
+    } else {
+        Some(seq as u64)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn clipboard_change_count() -> Option<u64> {
+    None
+}
+
+// Counts clipboard changes over a sliding one-minute window
+#[derive(Default)]
+pub struct ClipboardMonitor {
+    last_count: Option<u64>,
+    changes: VecDeque<(Instant, u64)>,
+}
+
+impl ClipboardMonitor {
+    // Samples the change counter and returns the number of changes seen in the
+    // last minute. Returns 0 where the platform has no change counter.
+    pub fn changes_per_minute(&mut self) -> u64 {
+        let now = Instant::now();
+
+        if let Some(count) = clipboard_change_count() {
+            if let Some(last) = self.last_count {
+                // The counter only moves forward; a smaller value means it was reset
+                let delta = count.saturating_sub(last);
+                if delta > 0 {
+                    self.changes.push_back((now, delta));
+                }
+            }
+            self.last_count = Some(count);
+        }
+
+        while let Some((at, _)) = self.changes.front() {
+            if now.duration_since(*at) > Duration::from_secs(60) {
+                self.changes.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        self.changes.iter().map(|(_, delta)| delta).sum()
+    }
+}
 
+// Looks up the bundle identifier of a running app by PID
+#[cfg(target_os = "macos")]
+pub fn bundle_id_for_pid(pid: u64) -> Option<String> {
     let script = format!(
-        r#"
-        tell application "{}"
-            if (count of windows) > 0 then
-                get URL of active tab of front window
-            else
-                return ""
-            end if
-        end tell
-        "#,
-        browser_script_name
+        "ObjC.import('AppKit'); \
+         const app = $.NSRunningApplication.runningApplicationWithProcessIdentifier({}); \
+         app.isNil() ? '' : ObjC.unwrap(app.bundleIdentifier)",
+        pid
     );
 
     let output = Command::new("osascript")
-        .args(&["-e", &script])
+        .args(["-l", "JavaScript", "-e", &script])
         .output()
         .ok()?;
 
     if output.status.success() {
-        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if !url.is_empty() {
-            return Some(url);
+        let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !id.is_empty() {
+            return Some(id);
+        }
+    }
+
+    None
+}
+
+// Bundle ids only exist on macOS
+#[cfg(not(target_os = "macos"))]
+pub fn bundle_id_for_pid(_pid: u64) -> Option<String> {
+    None
+}
+
+// Full path of a process's executable, or None when it can't be resolved,
+// e.g. the process has exited or belongs to another user
+#[cfg(target_os = "macos")]
+pub fn executable_path(pid: u64) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    // PROC_PIDPATHINFO_MAXSIZE
+    const MAX_PATH_BYTES: usize = 4 * 1024;
+
+    let mut buffer = vec![0u8; MAX_PATH_BYTES];
+    let len = unsafe { libc::proc_pidpath(pid as i32, buffer.as_mut_ptr().cast(), MAX_PATH_BYTES as u32) };
+    (len > 0).then(|| PathBuf::from(std::ffi::OsStr::from_bytes(&buffer[..len as usize])))
+}
+
+#[cfg(target_os = "windows")]
+pub fn executable_path(pid: u64) -> Option<PathBuf> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, u32::try_from(pid).ok()?) };
+    if process.is_null() {
+        return None;
+    }
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    let ok = unsafe { QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len) };
+    unsafe { CloseHandle(process) };
+    (ok != 0).then(|| PathBuf::from(String::from_utf16_lossy(&buffer[..len as usize])))
+}
+
+#[cfg(target_os = "linux")]
+pub fn executable_path(pid: u64) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{}/exe", pid)).ok()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn executable_path(_pid: u64) -> Option<PathBuf> {
+    None
+}
+
+// What identifies the app owning a window, beyond its display name
+#[derive(Clone)]
+pub struct AppIdentity {
+    // The most stable of the below, falling back to the app name
+    pub app_id: String,
+    pub executable_path: Option<PathBuf>,
+    // macOS only
+    pub bundle_id: Option<String>,
+}
+
+// App names are localized and can collide, so app_id prefers the bundle id on
+// macOS and the executable path elsewhere. `process_path` is what the window
+// lookup reported, used when the PID can't be resolved.
+pub fn resolve_app(pid: u64, process_path: &Path, app_name: &str) -> AppIdentity {
+    let bundle_id = bundle_id_for_pid(pid);
+    let executable_path = executable_path(pid)
+        .or_else(|| (!process_path.as_os_str().is_empty()).then(|| process_path.to_path_buf()));
+    let app_id = bundle_id
+        .clone()
+        .or_else(|| executable_path.as_ref().map(|path| path.to_string_lossy().to_string()))
+        .unwrap_or_else(|| app_name.to_string());
+    AppIdentity {
+        app_id,
+        executable_path,
+        bundle_id,
+    }
+}
+
+// Returns a stable identifier for the app owning a window
+pub fn app_identity(pid: u64, process_path: &Path, app_name: &str) -> String {
+    resolve_app(pid, process_path, app_name).app_id
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BatteryStatus {
+    pub percent: u8,
+    // True when on external power (charging or full)
+    pub charging: bool,
+}
+
+// Returns the battery charge and power source, or None on machines without a battery
+#[cfg(target_os = "macos")]
+pub fn battery_status() -> Option<BatteryStatus> {
+    // e.g. "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=123)\t85%; discharging; ..."
+    let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+
+    let percent_end = text.find('%')?;
+    let percent_start = text[..percent_end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let percent = text[percent_start..percent_end].parse().ok()?;
+
+    Some(BatteryStatus {
+        percent,
+        charging: text.contains("AC Power"),
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn battery_status() -> Option<BatteryStatus> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 128 = no system battery, 255 = unknown charge
+    if status.BatteryFlag == 128 || status.BatteryLifePercent == 255 {
+        return None;
+    }
+
+    Some(BatteryStatus {
+        percent: status.BatteryLifePercent,
+        charging: status.ACLineStatus == 1,
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn battery_status() -> Option<BatteryStatus> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_battery = std::fs::read_to_string(path.join("type"))
+            .map(|t| t.trim() == "Battery")
+            .unwrap_or(false);
+        if !is_battery {
+            continue;
         }
+
+        let percent = std::fs::read_to_string(path.join("capacity")).ok()?.trim().parse().ok()?;
+        let status = std::fs::read_to_string(path.join("status")).unwrap_or_default();
+        return Some(BatteryStatus {
+            percent,
+            charging: status.trim() != "Discharging",
+        });
+    }
+    None
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn battery_status() -> Option<BatteryStatus> {
+    None
+}
+
+// Cumulative CPU time counters since boot, in platform-specific ticks
+#[derive(Clone, Copy)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn cpu_times() -> Option<CpuTimes> {
+    // First line: "cpu  user nice system idle iowait irq softirq steal guest guest_nice"
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let ticks: Vec<u64> = stat
+        .lines()
+        .next()?
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .filter_map(|t| t.parse().ok())
+        .collect();
+    if ticks.len() < 5 {
+        return None;
+    }
+    let total: u64 = ticks.iter().sum();
+    let idle = ticks[3] + ticks[4];
+    Some(CpuTimes {
+        busy: total - idle,
+        total,
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn cpu_times() -> Option<CpuTimes> {
+    use windows_sys::Win32::Foundation::FILETIME;
+    use windows_sys::Win32::System::Threading::GetSystemTimes;
+
+    let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+    let (mut idle, mut kernel, mut user) = (zero, zero, zero);
+    if unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) } == 0 {
+        return None;
+    }
+    let ticks = |t: FILETIME| (u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime);
+    // Kernel time includes idle time
+    let total = ticks(kernel) + ticks(user);
+    Some(CpuTimes {
+        busy: total.saturating_sub(ticks(idle)),
+        total,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn cpu_times() -> Option<CpuTimes> {
+    None
+}
+
+// CPU time the calling thread has used so far, user and kernel combined
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(target_os = "windows")]
+pub fn thread_cpu_time() -> Option<Duration> {
+    use windows_sys::Win32::Foundation::FILETIME;
+    use windows_sys::Win32::System::Threading::{GetCurrentThread, GetThreadTimes};
+
+    let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+    let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+    if unsafe { GetThreadTimes(GetCurrentThread(), &mut created, &mut exited, &mut kernel, &mut user) } == 0 {
+        return None;
+    }
+    // FILETIMEs count 100ns intervals
+    let ticks = |t: FILETIME| (u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime);
+    Some(Duration::from_nanos((ticks(kernel) + ticks(user)) * 100))
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+// Overall CPU usage percent for platforms without cumulative counters: the sum
+// of per-process usage from ps, spread over the logical cores
+#[cfg(target_os = "macos")]
+pub fn cpu_usage_estimate() -> Option<f32> {
+    let output = Command::new("ps").args(["-A", "-o", "%cpu="]).output().ok()?;
+    let total: f32 = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse::<f32>().ok())
+        .sum();
+    let cores: f32 = sysctl_value("hw.logicalcpu")?.parse().ok()?;
+    Some((total / cores).min(100.0))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn cpu_usage_estimate() -> Option<f32> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn sysctl_value(name: &str) -> Option<String> {
+    let output = Command::new("sysctl").args(["-n", name]).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn memory_info() -> Option<MemoryInfo> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field_kb = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|l| l.starts_with(name))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    };
+    let total = field_kb("MemTotal:")? * 1024;
+    let available = field_kb("MemAvailable:")? * 1024;
+    Some(MemoryInfo {
+        total_bytes: total,
+        used_bytes: total.saturating_sub(available),
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn memory_info() -> Option<MemoryInfo> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    Some(MemoryInfo {
+        total_bytes: status.ullTotalPhys,
+        used_bytes: status.ullTotalPhys.saturating_sub(status.ullAvailPhys),
+    })
+}
+
+#[cfg(target_os = "macos")]
+pub fn memory_info() -> Option<MemoryInfo> {
+    let total: u64 = sysctl_value("hw.memsize")?.parse().ok()?;
+
+    // e.g. "Mach Virtual Memory Statistics: (page size of 16384 bytes)\nPages free:  12345.\n..."
+    let output = Command::new("vm_stat").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let page_size: u64 = text
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let pages = |name: &str| -> u64 {
+        text.lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.rsplit(':').next())
+            .and_then(|v| v.trim().trim_end_matches('.').parse().ok())
+            .unwrap_or(0)
+    };
+    let available = (pages("Pages free") + pages("Pages inactive") + pages("Pages speculative")) * page_size;
+    Some(MemoryInfo {
+        total_bytes: total,
+        used_bytes: total.saturating_sub(available),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn memory_info() -> Option<MemoryInfo> {
+    None
+}
+
+// Resident memory of this process
+#[cfg(target_os = "linux")]
+pub fn process_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "windows")]
+pub fn process_memory_bytes() -> Option<u64> {
+    use windows_sys::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    if unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) } == 0 {
+        return None;
     }
+    Some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_memory_bytes() -> Option<u64> {
+    let output = Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kb: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kb * 1024)
+}
 
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn process_memory_bytes() -> Option<u64> {
     None
 }
+
+// Well-known public DNS resolvers, probed by IP so the check doesn't depend on DNS
+const CONNECTIVITY_PROBES: [&str; 2] = ["1.1.1.1:53", "8.8.8.8:53"];
+
+// Best-effort internet connectivity check: can we open a TCP connection to a
+// public resolver? Says nothing about the local backend, which is always reachable.
+pub fn is_online() -> bool {
+    CONNECTIVITY_PROBES.iter().any(|probe| {
+        probe
+            .parse()
+            .ok()
+            .and_then(|addr| std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(1500)).ok())
+            .is_some()
+    })
+}
+
+// Kind of network the default route goes over: "wifi", "ethernet" or "unknown"
+#[cfg(target_os = "macos")]
+pub fn connection_type() -> &'static str {
+    let interface = Command::new("route")
+        .args(["-n", "get", "default"])
+        .output()
+        .ok()
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("interface:").map(|i| i.trim().to_string()))
+        });
+    let Some(interface) = interface else {
+        return "unknown";
+    };
+
+    // Map the device (e.g. en0) to its hardware port name
+    let Ok(output) = Command::new("networksetup").arg("-listallhardwareports").output() else {
+        return "unknown";
+    };
+    let ports = String::from_utf8_lossy(&output.stdout);
+    let mut port_name = "";
+    for line in ports.lines() {
+        if let Some(name) = line.strip_prefix("Hardware Port: ") {
+            port_name = name;
+        } else if line.strip_prefix("Device: ") == Some(interface.as_str()) {
+            return if port_name.contains("Wi-Fi") || port_name.contains("AirPort") {
+                "wifi"
+            } else if port_name.contains("Ethernet") || port_name.contains("LAN") {
+                "ethernet"
+            } else {
+                "unknown"
+            };
+        }
+    }
+    "unknown"
+}
+
+#[cfg(target_os = "linux")]
+pub fn connection_type() -> &'static str {
+    // The interface carrying the default route has destination 00000000
+    let Ok(routes) = std::fs::read_to_string("/proc/net/route") else {
+        return "unknown";
+    };
+    let interface = routes.lines().skip(1).find_map(|line| {
+        let mut cols = line.split_whitespace();
+        let name = cols.next()?;
+        (cols.next()? == "00000000").then(|| name.to_string())
+    });
+
+    match interface {
+        Some(name) if Path::new("/sys/class/net").join(&name).join("wireless").exists() => "wifi",
+        Some(_) => "ethernet",
+        None => "unknown",
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn connection_type() -> &'static str {
+    "unknown"
+}
+
+// Whether a privacy-sensitive device (microphone or camera) is currently in use
+// by any app. Only the on/off state is read, never any audio or video.
+// None means the platform doesn't expose it, which includes the camera on Linux.
+#[cfg(target_os = "windows")]
+fn consent_store_in_use(capability: &str) -> Option<bool> {
+    // Windows records LastUsedTimeStop = 0 for apps currently using the device
+    let key = format!(
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\{}",
+        capability
+    );
+    let output = Command::new("reg")
+        .args(["query", &key, "/s", "/v", "LastUsedTimeStop"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(text.lines().any(|line| {
+        let mut cols = line.split_whitespace();
+        cols.next() == Some("LastUsedTimeStop") && cols.nth(1) == Some("0x0")
+    }))
+}
+
+#[cfg(target_os = "windows")]
+pub fn microphone_in_use() -> Option<bool> {
+    consent_store_in_use("microphone")
+}
+
+#[cfg(target_os = "windows")]
+pub fn camera_in_use() -> Option<bool> {
+    consent_store_in_use("webcam")
+}
+
+#[cfg(target_os = "linux")]
+pub fn microphone_in_use() -> Option<bool> {
+    // ALSA exposes each capture substream's state, e.g. /proc/asound/card0/pcm0c/sub0/status
+    let cards = std::fs::read_dir("/proc/asound").ok()?;
+    let mut found_capture_device = false;
+
+    for card in cards.flatten() {
+        let Ok(pcms) = std::fs::read_dir(card.path()) else {
+            continue;
+        };
+        for pcm in pcms.flatten() {
+            let name = pcm.file_name().to_string_lossy().to_string();
+            if !(name.starts_with("pcm") && name.ends_with('c')) {
+                continue;
+            }
+            found_capture_device = true;
+            let Ok(subs) = std::fs::read_dir(pcm.path()) else {
+                continue;
+            };
+            for sub in subs.flatten() {
+                let status = std::fs::read_to_string(sub.path().join("status")).unwrap_or_default();
+                if status.contains("RUNNING") {
+                    return Some(true);
+                }
+            }
+        }
+    }
+
+    found_capture_device.then_some(false)
+}
+
+// CoreAudio and CoreMediaIO property access, for the "in use" checks below.
+// Both report DeviceIsRunningSomewhere, which is set while any process uses the device.
+#[cfg(target_os = "macos")]
+mod device_usage {
+    use std::ffi::c_void;
+
+    const fn four_cc(code: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*code)
+    }
+
+    const SYSTEM_OBJECT: u32 = 1;
+    const PROPERTY_DEVICES: u32 = four_cc(b"dev#");
+    const PROPERTY_STREAMS: u32 = four_cc(b"stm#");
+    const PROPERTY_RUNNING_SOMEWHERE: u32 = four_cc(b"gone");
+    const SCOPE_GLOBAL: u32 = four_cc(b"glob");
+    const SCOPE_INPUT: u32 = four_cc(b"inpt");
+    const ELEMENT_MAIN: u32 = 0;
+
+    // Same layout as AudioObjectPropertyAddress and CMIOObjectPropertyAddress
+    #[repr(C)]
+    struct PropertyAddress {
+        selector: u32,
+        scope: u32,
+        element: u32,
+    }
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyDataSize(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn AudioObjectGetPropertyData(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    #[link(name = "CoreMediaIO", kind = "framework")]
+    extern "C" {
+        fn CMIOObjectGetPropertyDataSize(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: *mut u32,
+        ) -> i32;
+        fn CMIOObjectGetPropertyData(
+            object: u32,
+            address: *const PropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            size: u32,
+            used: *mut u32,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    fn address(selector: u32, scope: u32) -> PropertyAddress {
+        PropertyAddress {
+            selector,
+            scope,
+            element: ELEMENT_MAIN,
+        }
+    }
+
+    // The two frameworks share a property model but differ slightly in signatures
+    #[derive(Clone, Copy)]
+    enum Api {
+        CoreAudio,
+        CoreMediaIO,
+    }
+
+    impl Api {
+        fn size(self, object: u32, address: &PropertyAddress) -> Option<u32> {
+            let mut size = 0;
+            let status = unsafe {
+                match self {
+                    Api::CoreAudio => AudioObjectGetPropertyDataSize(object, address, 0, std::ptr::null(), &mut size),
+                    Api::CoreMediaIO => CMIOObjectGetPropertyDataSize(object, address, 0, std::ptr::null(), &mut size),
+                }
+            };
+            (status == 0).then_some(size)
+        }
+
+        // Reads a property made of u32 values, e.g. a device list or a flag
+        fn read(self, object: u32, address: &PropertyAddress) -> Option<Vec<u32>> {
+            let size = self.size(object, address)?;
+            let mut values = vec![0u32; size as usize / std::mem::size_of::<u32>()];
+            let mut used = size;
+            let data = values.as_mut_ptr().cast();
+            let status = unsafe {
+                match self {
+                    Api::CoreAudio => AudioObjectGetPropertyData(object, address, 0, std::ptr::null(), &mut used, data),
+                    Api::CoreMediaIO => {
+                        CMIOObjectGetPropertyData(object, address, 0, std::ptr::null(), size, &mut used, data)
+                    }
+                }
+            };
+            if status != 0 {
+                return None;
+            }
+            values.truncate(used as usize / std::mem::size_of::<u32>());
+            Some(values)
+        }
+
+        // Some(true) if any matching device is in use, Some(false) if none are,
+        // None when there are no matching devices to ask
+        fn any_running(self, is_candidate: impl Fn(u32) -> bool) -> Option<bool> {
+            let devices = self.read(SYSTEM_OBJECT, &address(PROPERTY_DEVICES, SCOPE_GLOBAL))?;
+            let mut found = false;
+            for device in devices.into_iter().filter(|device| is_candidate(*device)) {
+                found = true;
+                let running = self.read(device, &address(PROPERTY_RUNNING_SOMEWHERE, SCOPE_GLOBAL));
+                if running.is_some_and(|flag| flag.first().is_some_and(|flag| *flag != 0)) {
+                    return Some(true);
+                }
+            }
+            found.then_some(false)
+        }
+    }
+
+    pub fn microphone() -> Option<bool> {
+        // Only devices with input streams can record
+        let has_input = |device| {
+            Api::CoreAudio
+                .size(device, &address(PROPERTY_STREAMS, SCOPE_INPUT))
+                .is_some_and(|size| size > 0)
+        };
+        Api::CoreAudio.any_running(has_input)
+    }
+
+    pub fn camera() -> Option<bool> {
+        Api::CoreMediaIO.any_running(|_| true)
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn microphone_in_use() -> Option<bool> {
+    device_usage::microphone()
+}
+
+#[cfg(target_os = "macos")]
+pub fn camera_in_use() -> Option<bool> {
+    device_usage::camera()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn microphone_in_use() -> Option<bool> {
+    None
+}
+
+// Linux has no system-wide "camera in use" flag to read without opening the device
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn camera_in_use() -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+}
+
+// Seconds since the last keyboard/mouse input, or None when it can't be determined
+#[cfg(target_os = "macos")]
+pub fn seconds_since_last_input() -> Option<u64> {
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const ANY_INPUT_EVENT_TYPE: u32 = u32::MAX;
+    let secs = unsafe {
+        CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT_TYPE)
+    };
+    (secs >= 0.0).then_some(secs as u64)
+}
+
+#[cfg(target_os = "windows")]
+pub fn seconds_since_last_input() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are 32-bit tick counts, so wrapping_sub handles the ~49 day rollover
+    let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    Some(u64::from(idle_ms) / 1000)
+}
+
+// Uses xprintidle, which queries the X11 screensaver extension. Not available on Wayland.
+#[cfg(target_os = "linux")]
+pub fn seconds_since_last_input() -> Option<u64> {
+    let output = Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let idle_ms: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(idle_ms / 1000)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn seconds_since_last_input() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSessionCopyCurrentDictionary() -> *const std::ffi::c_void;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFStringCreateWithCString(
+        alloc: *const std::ffi::c_void,
+        c_str: *const std::ffi::c_char,
+        encoding: u32,
+    ) -> *const std::ffi::c_void;
+    fn CFDictionaryGetValue(dict: *const std::ffi::c_void, key: *const std::ffi::c_void) -> *const std::ffi::c_void;
+    fn CFBooleanGetValue(boolean: *const std::ffi::c_void) -> u8;
+    fn CFRelease(cf: *const std::ffi::c_void);
+}
+
+// Whether the screen is locked, or None when it can't be determined.
+// The session dictionary only carries CGSSessionScreenIsLocked while locked.
+#[cfg(target_os = "macos")]
+pub fn is_screen_locked() -> Option<bool> {
+    const UTF8_ENCODING: u32 = 0x0800_0100;
+    unsafe {
+        let session = CGSessionCopyCurrentDictionary();
+        if session.is_null() {
+            return None;
+        }
+        let key = CFStringCreateWithCString(std::ptr::null(), c"CGSSessionScreenIsLocked".as_ptr(), UTF8_ENCODING);
+        let value = CFDictionaryGetValue(session, key);
+        let locked = !value.is_null() && CFBooleanGetValue(value) != 0;
+        CFRelease(key);
+        CFRelease(session);
+        Some(locked)
+    }
+}
+
+// The input desktop can't be opened while the lock screen (or UAC prompt) owns it
+#[cfg(target_os = "windows")]
+pub fn is_screen_locked() -> Option<bool> {
+    use windows_sys::Win32::System::StationsAndDesktops::{CloseDesktop, OpenInputDesktop, DESKTOP_SWITCHDESKTOP};
+
+    let desktop = unsafe { OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP) };
+    if desktop.is_null() {
+        return Some(true);
+    }
+    unsafe { CloseDesktop(desktop) };
+    Some(false)
+}
+
+// Asks logind for the session's LockedHint, which desktop lock screens set
+#[cfg(target_os = "linux")]
+pub fn is_screen_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+    let output = Command::new("loginctl")
+        .args(["show-session", &session, "-p", "LockedHint", "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn is_screen_locked() -> Option<bool> {
+    None
+}
+
+// Whether the OS is set to a dark appearance, or None when it can't be determined.
+// AppleInterfaceStyle is only present (as "Dark") while dark mode is on.
+#[cfg(target_os = "macos")]
+pub fn prefers_dark_theme() -> Option<bool> {
+    let output = Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+        .ok()?;
+    Some(output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "Dark")
+}
+
+// Apps follow AppsUseLightTheme, a DWORD that is 0 in dark mode
+#[cfg(target_os = "windows")]
+pub fn prefers_dark_theme() -> Option<bool> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let value = text.lines().find(|line| line.contains("AppsUseLightTheme"))?;
+    Some(value.split_whitespace().last()? == "0x0")
+}
+
+// GNOME-style desktops publish color-scheme; older ones only have a theme
+// name, where dark variants conventionally carry "dark" in the name
+#[cfg(target_os = "linux")]
+pub fn prefers_dark_theme() -> Option<bool> {
+    let gsettings = |key: &str| {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", key])
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().trim_matches('\'').to_lowercase())
+    };
+    match gsettings("color-scheme").as_deref() {
+        Some("prefer-dark") => return Some(true),
+        Some("prefer-light") => return Some(false),
+        _ => {}
+    }
+    gsettings("gtk-theme").map(|theme| theme.contains("dark"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn prefers_dark_theme() -> Option<bool> {
+    None
+}
+
+// A user-facing application with at least one window
+pub struct RunningApp {
+    pub pid: u32,
+    pub name: String,
+    pub path: Option<PathBuf>,
+}
+
+// Apps with a regular activation policy are the ones shown in the Dock;
+// agents and daemons are left out
+#[cfg(target_os = "macos")]
+const RUNNING_APPS_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+const apps = $.NSWorkspace.sharedWorkspace.runningApplications;
+const lines = [];
+for (let i = 0; i < apps.count; i++) {
+    const app = apps.objectAtIndex(i);
+    if (app.activationPolicy !== 0) continue;
+    const path = app.executableURL.isNil() ? '' : ObjC.unwrap(app.executableURL.path);
+    lines.push(app.processIdentifier + '\t' + ObjC.unwrap(app.localizedName) + '\t' + path);
+}
+lines.join('\n');
+"#;
+
+// Lists running applications the user can see, or None if they can't be enumerated
+#[cfg(target_os = "macos")]
+pub fn running_apps() -> Option<Vec<RunningApp>> {
+    let output = Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", RUNNING_APPS_SCRIPT])
+        .env("LANG", "en_US.UTF-8")
+        .env("LC_ALL", "en_US.UTF-8")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // "<pid>\t<name>\t<executable path>" per line
+    let text = decode_output(&output.stdout);
+    let apps = text
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let pid = parts.next()?.parse().ok()?;
+            let name = parts.next()?.to_string();
+            let path = parts.next().filter(|path| !path.is_empty()).map(PathBuf::from);
+            Some(RunningApp { pid, name, path })
+        })
+        .collect();
+    Some(apps)
+}
+
+// Processes owning a visible, unowned top-level window with a title, i.e. the
+// windows that get a taskbar button. Tool windows are skipped.
+#[cfg(target_os = "windows")]
+pub fn running_apps() -> Option<Vec<RunningApp>> {
+    use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindow, GetWindowLongW, GetWindowTextLengthW, GetWindowThreadProcessId, IsWindowVisible,
+        GWL_EXSTYLE, GW_OWNER, WS_EX_TOOLWINDOW,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let pids = &mut *(lparam as *mut Vec<u32>);
+        let tool_window = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW != 0;
+        if IsWindowVisible(hwnd) != 0
+            && GetWindow(hwnd, GW_OWNER).is_null()
+            && GetWindowTextLengthW(hwnd) > 0
+            && !tool_window
+        {
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if pid != 0 && !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+        1
+    }
+
+    let mut pids: Vec<u32> = Vec::new();
+    if unsafe { EnumWindows(Some(collect), &mut pids as *mut Vec<u32> as LPARAM) } == 0 {
+        return None;
+    }
+
+    let apps = pids
+        .into_iter()
+        .filter_map(|pid| {
+            let path = executable_path(u64::from(pid))?;
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            Some(RunningApp { pid, name, path: Some(path) })
+        })
+        .collect();
+    Some(apps)
+}
+
+// Reads the window manager's client list (_NET_CLIENT_LIST), i.e. the windows a
+// taskbar would show, skipping any that ask to be left off it. Names come from
+// WM_CLASS, matching the app names active-win-pos-rs reports. Only X11 and
+// XWayland windows are visible this way.
+#[cfg(target_os = "linux")]
+pub fn running_apps() -> Option<Vec<RunningApp>> {
+    use xcb::x;
+
+    let (conn, screen) = xcb::Connection::connect(None).ok()?;
+    let root = conn.get_setup().roots().nth(screen as usize)?.root();
+
+    let atom = |name: &[u8]| {
+        let cookie = conn.send_request(&x::InternAtom { only_if_exists: true, name });
+        conn.wait_for_reply(cookie).ok().map(|reply| reply.atom())
+    };
+    let property = |window: x::Window, property: x::Atom, r#type: x::Atom, long_length: u32| {
+        let cookie = conn.send_request(&x::GetProperty {
+            delete: false,
+            window,
+            property,
+            r#type,
+            long_offset: 0,
+            long_length,
+        });
+        conn.wait_for_reply(cookie).ok()
+    };
+
+    let client_list = atom(b"_NET_CLIENT_LIST").filter(|atom| *atom != x::ATOM_NONE)?;
+    let wm_pid = atom(b"_NET_WM_PID")?;
+    let wm_state = atom(b"_NET_WM_STATE")?;
+    let skip_taskbar = atom(b"_NET_WM_STATE_SKIP_TASKBAR")?;
+
+    let windows = property(root, client_list, x::ATOM_WINDOW, 4096)?;
+    let mut apps: Vec<RunningApp> = Vec::new();
+    for &window in windows.value::<x::Window>() {
+        let skipped = property(window, wm_state, x::ATOM_ATOM, 64)
+            .is_some_and(|state| state.value::<x::Atom>().contains(&skip_taskbar));
+        if skipped {
+            continue;
+        }
+        let Some(pid) = property(window, wm_pid, x::ATOM_CARDINAL, 1).and_then(|reply| reply.value::<u32>().first().copied())
+        else {
+            continue;
+        };
+        if apps.iter().any(|app| app.pid == pid) {
+            continue;
+        }
+        // WM_CLASS is "instance\0class\0"; the class is the app's name
+        let Some(name) = property(window, x::ATOM_WM_CLASS, x::ATOM_STRING, 256).and_then(|reply| {
+            String::from_utf8_lossy(reply.value::<u8>())
+                .split('\0')
+                .rfind(|part| !part.is_empty())
+                .map(str::to_string)
+        }) else {
+            continue;
+        };
+        let path = std::fs::read_link(format!("/proc/{}/exe", pid)).ok();
+        apps.push(RunningApp { pid, name, path });
+    }
+    Some(apps)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn running_apps() -> Option<Vec<RunningApp>> {
+    None
+}
+
+// The full command line of a running process, or None if it isn't running or
+// can't be inspected
+#[cfg(target_os = "linux")]
+pub fn process_command_line(pid: u32) -> Option<String> {
+    let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    // Zombies have an empty command line
+    if raw.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&raw).replace('\0', " ").trim_end().to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_command_line(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let command_line = decode_output(&output.stdout);
+    (!command_line.is_empty()).then_some(command_line)
+}
+
+// Other processes' command lines aren't exposed by a simple Win32 call, so ask WMI
+#[cfg(target_os = "windows")]
+pub fn process_command_line(pid: u32) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let query = format!("(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine", pid);
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &query])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!command_line.is_empty()).then_some(command_line)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn process_command_line(_pid: u32) -> Option<String> {
+    None
+}
+
+// Asks a process to exit, killing it if it is still running after `grace`.
+// Returns whether it is gone.
+#[cfg(unix)]
+pub fn end_process(pid: u32, grace: Duration) -> bool {
+    let pid = pid as libc::pid_t;
+    // SAFETY: kill has no memory-safety preconditions
+    let alive = || unsafe { libc::kill(pid, 0) } == 0;
+
+    unsafe { libc::kill(pid, libc::SIGTERM) };
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        if !alive() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    unsafe { libc::kill(pid, libc::SIGKILL) };
+    std::thread::sleep(Duration::from_millis(100));
+    !alive()
+}
+
+// A windowless console process can't be asked to close, so it is terminated
+// straight away; `grace` is how long to wait for it to go
+#[cfg(windows)]
+pub fn end_process(pid: u32, grace: Duration) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, WAIT_OBJECT_0};
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, TerminateProcess, WaitForSingleObject, PROCESS_SYNCHRONIZE, PROCESS_TERMINATE,
+    };
+
+    let process = unsafe { OpenProcess(PROCESS_TERMINATE | PROCESS_SYNCHRONIZE, 0, pid) };
+    if process.is_null() {
+        return false;
+    }
+    let ended = unsafe {
+        TerminateProcess(process, 1) != 0 && WaitForSingleObject(process, grace.as_millis() as u32) == WAIT_OBJECT_0
+    };
+    unsafe { CloseHandle(process) };
+    ended
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn end_process(_pid: u32, _grace: Duration) -> bool {
+    false
+}
+
+// Broad kind of activity, sent with each update so the backend can total
+// e.g. time spent in meetings
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Meeting,
+    Browser,
+    Editor,
+    Communication,
+    Other,
+}
+
+impl Category {
+    // Counted towards the daily summary's focus score
+    pub fn is_productive(self) -> bool {
+        matches!(self, Category::Editor | Category::Meeting)
+    }
+}
+
+// Meeting apps count as a meeting whenever they are focused
+const MEETING_APPS: [&str; 5] = ["zoom", "webex", "facetime", "gotomeeting", "whereby"];
+// Chat apps that host calls: only the call windows count as meetings
+const CALL_APPS: [&str; 3] = ["teams", "slack", "discord"];
+const CALL_TITLES: [&str; 4] = ["meeting", "call with", "huddle", "voice connected"];
+const MEETING_URLS: [&str; 6] = [
+    "meet.google.com",
+    "zoom.us/j/",
+    "zoom.us/wc/",
+    "teams.microsoft.com/l/meetup-join",
+    "teams.live.com/meet",
+    "webex.com/meet",
+];
+const COMMUNICATION_APPS: [&str; 11] = [
+    "teams", "slack", "discord", "messages", "mail", "outlook", "thunderbird", "telegram", "whatsapp", "signal",
+    "messenger",
+];
+const COMMUNICATION_URLS: [&str; 6] = [
+    "mail.google.com",
+    "outlook.live.com",
+    "outlook.office.com",
+    "app.slack.com",
+    "discord.com/channels",
+    "web.whatsapp.com",
+];
+const EDITOR_APPS: [&str; 13] = [
+    "code", "cursor", "visual studio", "xcode", "intellij", "idea", "pycharm", "webstorm", "android studio",
+    "sublime", "vim", "emacs", "notepad++",
+];
+const BROWSER_APPS: [&str; 7] = ["chrome", "chromium", "firefox", "safari", "edge", "brave", "vivaldi"];
+// Names short enough to turn up inside unrelated app names, so matched whole
+const EDITOR_APP_NAMES: [&str; 1] = ["zed"];
+const BROWSER_APP_NAMES: [&str; 1] = ["arc"];
+
+fn contains_any(haystack: &str, needles: &[&str]) -> bool {
+    needles.iter().any(|needle| haystack.contains(needle))
+}
+
+// Classifies the focused window from its app name, title and URL. Only
+// substring checks against short tables, so it is cheap enough for every tick.
+pub fn activity_category(app_name: &str, title: &str, url: Option<&str>) -> Category {
+    let app = app_name.to_lowercase();
+    let title = title.to_lowercase();
+    let url = url.map(str::to_lowercase).unwrap_or_default();
+
+    if contains_any(&app, &MEETING_APPS)
+        || (contains_any(&app, &CALL_APPS) && contains_any(&title, &CALL_TITLES))
+        || contains_any(&url, &MEETING_URLS)
+    {
+        return Category::Meeting;
+    }
+    if contains_any(&app, &COMMUNICATION_APPS) || contains_any(&url, &COMMUNICATION_URLS) {
+        return Category::Communication;
+    }
+    if contains_any(&app, &EDITOR_APPS) || EDITOR_APP_NAMES.contains(&app.as_str()) {
+        return Category::Editor;
+    }
+    if !url.is_empty() || contains_any(&app, &BROWSER_APPS) || BROWSER_APP_NAMES.contains(&app.as_str()) {
+        return Category::Browser;
+    }
+    Category::Other
+}
+
+// A user-defined rule, checked before the built-in ones. Each field that is set
+// must appear (case-insensitively) in the corresponding part of the activity.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub category: Category,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl CategoryRule {
+    pub fn is_empty(&self) -> bool {
+        self.app.is_none() && self.title.is_none() && self.url.is_none()
+    }
+
+    fn matches(&self, app_name: &str, title: &str, url: Option<&str>) -> bool {
+        let field = |pattern: &Option<String>, value: Option<&str>| match pattern {
+            Some(pattern) => value.is_some_and(|value| value.to_lowercase().contains(&pattern.to_lowercase())),
+            None => true,
+        };
+        !self.is_empty()
+            && field(&self.app, Some(app_name))
+            && field(&self.title, Some(title))
+            && field(&self.url, url)
+    }
+}
+
+// activity_category with the user's rules taking precedence
+pub fn classify_activity(rules: &[CategoryRule], app_name: &str, title: &str, url: Option<&str>) -> Category {
+    rules
+        .iter()
+        .find(|rule| rule.matches(app_name, title, url))
+        .map(|rule| rule.category)
+        .unwrap_or_else(|| activity_category(app_name, title, url))
+}
+
+// The user's timezone and language, for time-based advice from the backend
+#[derive(Serialize)]
+pub struct LocaleInfo {
+    // IANA name such as "Europe/Berlin"; None if the OS won't say
+    pub timezone: Option<String>,
+    // Current offset from UTC, including daylight saving
+    pub utc_offset_minutes: i32,
+    // BCP 47 tag such as "en-US"
+    pub locale: Option<String>,
+}
+
+pub fn locale_info() -> LocaleInfo {
+    LocaleInfo {
+        timezone: iana_time_zone::get_timezone().ok(),
+        utc_offset_minutes: chrono::Local::now().offset().local_minus_utc() / 60,
+        locale: system_locale(),
+    }
+}
+
+// Turns a POSIX locale such as "en_US.UTF-8" or a macOS one such as
+// "en_US@rg=gbzzzz" into "en-US". "C" and "POSIX" mean no locale was chosen.
+fn locale_tag(raw: &str) -> Option<String> {
+    let tag = raw.split(['.', '@']).next()?.trim().replace('_', "-");
+    (!tag.is_empty() && tag != "C" && tag != "POSIX").then_some(tag)
+}
+
+#[cfg(unix)]
+fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find_map(|value| locale_tag(&value))
+}
+
+// Apps launched from Finder get no LANG, so ask the user defaults first
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    Command::new("defaults")
+        .args(["read", "-g", "AppleLocale"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| locale_tag(&decode_output(&output.stdout)))
+        .or_else(env_locale)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_locale() -> Option<String> {
+    env_locale()
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    if len <= 1 {
+        return None;
+    }
+    // The length includes the terminating NUL
+    locale_tag(&String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn system_locale() -> Option<String> {
+    None
+}
+
+// The primary display as the windowing system reports it. Only Linux needs it;
+// the macOS and Windows tools find the primary display themselves.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct PrimaryDisplay {
+    // Output name such as "eDP-1", where known
+    pub name: Option<String>,
+    // Position and size in physical pixels
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Saves a PNG of the primary display to `path`. `primary` is only consulted
+// where the capture tool can't pick the primary display by itself.
+#[cfg(target_os = "macos")]
+pub fn capture_primary_display(path: &Path, _primary: Option<PrimaryDisplay>) -> Result<(), String> {
+    // -m: main display only, -x: no shutter sound
+    let status = Command::new("screencapture")
+        .args(["-m", "-x"])
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to run screencapture: {}", e))?;
+    if !status.success() || !path.exists() {
+        // Without the Screen Recording permission screencapture fails or saves nothing
+        return Err("screencapture failed; check the Screen Recording permission".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn capture_primary_display(path: &Path, _primary: Option<PrimaryDisplay>) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // Opt in to DPI awareness first, or the bounds are scaled down on high-DPI displays
+    let script = format!(
+        r#"
+        Add-Type -MemberDefinition '[DllImport("user32.dll")] public static extern bool SetProcessDPIAware();' -Name Dpi -Namespace Win32
+        [Win32.Dpi]::SetProcessDPIAware() | Out-Null
+        Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+        $bounds = [System.Windows.Forms.Screen]::PrimaryScreen.Bounds
+        $bitmap = New-Object System.Drawing.Bitmap $bounds.Width, $bounds.Height
+        $graphics = [System.Drawing.Graphics]::FromImage($bitmap)
+        $graphics.CopyFromScreen($bounds.Location, [System.Drawing.Point]::Empty, $bounds.Size)
+        $bitmap.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)
+        "#,
+        path.to_string_lossy().replace('\'', "''")
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+    if !output.status.success() || !path.exists() {
+        return Err(format!(
+            "Screen capture failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// Wayland doesn't let clients read the screen, so use grim there; under X11
+// the primary display's part of the root window is read directly.
+#[cfg(target_os = "linux")]
+pub fn capture_primary_display(path: &Path, primary: Option<PrimaryDisplay>) -> Result<(), String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new("grim");
+        if let Some(name) = primary.and_then(|display| display.name) {
+            command.args(["-o", &name]);
+        }
+        let status = command
+            .arg(path)
+            .status()
+            .map_err(|e| format!("Screenshots on Wayland need grim: {}", e))?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err("grim failed to capture the screen".to_string())
+        };
+    }
+
+    use xcb::x;
+
+    let (conn, screen) = xcb::Connection::connect(None).map_err(|e| format!("Failed to connect to X11: {}", e))?;
+    let screen = conn
+        .get_setup()
+        .roots()
+        .nth(screen as usize)
+        .ok_or_else(|| "No X11 screen".to_string())?;
+    let (x, y, width, height) = match primary {
+        Some(display) => (display.x, display.y, display.width, display.height),
+        None => (0, 0, screen.width_in_pixels() as u32, screen.height_in_pixels() as u32),
+    };
+
+    let cookie = conn.send_request(&x::GetImage {
+        format: x::ImageFormat::ZPixmap,
+        drawable: x::Drawable::Window(screen.root()),
+        x: x as i16,
+        y: y as i16,
+        width: width as u16,
+        height: height as u16,
+        plane_mask: u32::MAX,
+    });
+    let image = conn.wait_for_reply(cookie).map_err(|e| format!("Failed to read the screen: {}", e))?;
+    let data = image.data();
+    // 24/32-bit visuals come back as 4 bytes per pixel in BGRX order
+    if data.len() < (width * height * 4) as usize {
+        return Err(format!("Unsupported X11 pixel format (depth {})", image.depth()));
+    }
+    let rgb: Vec<u8> = data
+        .chunks_exact(4)
+        .take((width * height) as usize)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+        .collect();
+
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgb))
+        .map_err(|e| format!("Failed to write PNG: {}", e))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn capture_primary_display(_path: &Path, _primary: Option<PrimaryDisplay>) -> Result<(), String> {
+    Err("Screenshots aren't supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internationalized_hosts_become_punycode() {
+        let normalized = normalize_url("https://例え.jp");
+        assert_eq!(normalized, "https://xn--r8jz45g.jp/");
+        assert_eq!(normalize_url(&normalized), normalized);
+    }
+
+    #[test]
+    fn non_ascii_paths_are_percent_encoded_once() {
+        assert_eq!(
+            normalize_url("https://例え.jp/ページ"),
+            "https://xn--r8jz45g.jp/%E3%83%9A%E3%83%BC%E3%82%B8"
+        );
+        // Already encoded paths are left alone rather than encoded again
+        assert_eq!(normalize_url("https://example.com/caf%C3%A9"), "https://example.com/caf%C3%A9");
+        assert_eq!(normalize_url("https://example.com/café"), "https://example.com/caf%C3%A9");
+    }
+
+    #[test]
+    fn unparseable_urls_are_kept() {
+        assert_eq!(normalize_url("not a url"), "not a url");
+    }
+
+    #[test]
+    fn thread_cpu_time_counts_work_not_sleep() {
+        let start = thread_cpu_time().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let slept = thread_cpu_time().unwrap() - start;
+        let busy_until = Instant::now() + Duration::from_millis(100);
+        let mut spins = 0u64;
+        while Instant::now() < busy_until {
+            spins = std::hint::black_box(spins + 1);
+        }
+        let worked = thread_cpu_time().unwrap() - start - slept;
+        assert!(slept < Duration::from_millis(50), "sleeping used {:?}", slept);
+        assert!(worked >= Duration::from_millis(50), "spinning used {:?}", worked);
+    }
+
+    #[test]
+    fn decodes_utf8_output_exactly() {
+        assert_eq!(decode_output("ページ – Safari\n".as_bytes()), "ページ – Safari");
+    }
+
+    #[test]
+    fn decodes_invalid_utf8_lossily() {
+        assert_eq!(decode_output(b"caf\xe9 menu\n"), "caf\u{FFFD} menu");
+    }
+}
@@ -0,0 +1,118 @@
+use regex::Regex;
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Runtime};
+
+use crate::settings;
+
+// Built-in redactions applied to every window title before it leaves the shell
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+// Nine or more digits, optionally grouped by spaces or dashes (card, SSN, account numbers)
+static LONG_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d(?:[ -]?\d){8,}\b").unwrap());
+
+// The user's patterns from the store, compiled once per change
+static USER_PATTERNS: Mutex<Option<(Vec<String>, Vec<Regex>)>> = Mutex::new(None);
+
+// Redacts email addresses and long digit sequences, then anything matching `extra`
+pub fn sanitize_title(title: &str, extra: &[Regex]) -> String {
+    let title = EMAIL.replace_all(title, "[email]");
+    let mut title = LONG_NUMBER.replace_all(&title, "[number]").into_owned();
+    for pattern in extra {
+        title = pattern.replace_all(&title, "[redacted]").into_owned();
+    }
+    title
+}
+
+// Applies sanitize_title with the user's patterns, unless redaction is switched off
+pub fn redact_title<R: Runtime>(app: &AppHandle<R>, title: &str) -> String {
+    if !settings::get_bool(app, settings::REDACT_TITLES, true) {
+        return title.to_string();
+    }
+    let patterns = settings::title_redaction_patterns(app);
+    let mut cache = USER_PATTERNS.lock().unwrap();
+    if cache.as_ref().is_none_or(|(cached, _)| *cached != patterns) {
+        // Patterns are validated when saved, so failures here are hand-edited entries
+        let compiled = patterns.iter().filter_map(|p| Regex::new(p).ok()).collect();
+        *cache = Some((patterns, compiled));
+    }
+    let extra = cache.as_ref().map(|(_, compiled)| compiled.as_slice()).unwrap_or_default();
+    sanitize_title(title, extra)
+}
+
+// Checks a user-supplied pattern before it is stored
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    Regex::new(pattern)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid redaction pattern '{}': {}", pattern, e))
+}
+
+// Query parameters kept by default when stripping, e.g. YouTube's video id
+pub const DEFAULT_URL_PARAM_ALLOWLIST: [&str; 1] = ["v"];
+
+// Drops the query (except allowlisted parameters), the fragment and any
+// credentials from a URL, keeping scheme, host, port and path. Strings that
+// don't parse as URLs are returned unchanged.
+pub fn strip_url_params(raw: &str, keep: &[String]) -> String {
+    let Ok(mut url) = url::Url::parse(raw) else {
+        return raw.to_string();
+    };
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| keep.iter().any(|k| k == name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    url.set_query(None);
+    url.set_fragment(None);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    if !kept.is_empty() {
+        url.query_pairs_mut().extend_pairs(kept);
+    }
+    url.to_string()
+}
+
+// Applies strip_url_params with the user's allowlist, unless stripping is switched off
+pub fn sanitize_url<R: Runtime>(app: &AppHandle<R>, url: &str) -> String {
+    if !settings::get_bool(app, settings::STRIP_URL_PARAMS, true) {
+        return url.to_string();
+    }
+    strip_url_params(url, &settings::url_param_allowlist(app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_the_address_in_a_gmail_tab() {
+        assert_eq!(
+            sanitize_title("Inbox (3) - jane.doe+work@gmail.com - Gmail", &[]),
+            "Inbox (3) - [email] - Gmail"
+        );
+    }
+
+    #[test]
+    fn redacts_a_grouped_card_number() {
+        assert_eq!(
+            sanitize_title("Invoice 4111 1111 1111 1111.pdf", &[]),
+            "Invoice [number].pdf"
+        );
+    }
+
+    #[test]
+    fn applies_user_patterns_after_the_built_ins() {
+        let extra = [Regex::new(r"(?i)project \w+").unwrap()];
+        assert_eq!(
+            sanitize_title("Project Falcon notes - bob@example.org", &extra),
+            "[redacted] notes - [email]"
+        );
+    }
+
+    #[test]
+    fn redacts_nine_digits_but_not_eight() {
+        assert_eq!(sanitize_title("Account 123456789", &[]), "Account [number]");
+        assert_eq!(sanitize_title("Order 12345678", &[]), "Order 12345678");
+        assert_eq!(sanitize_title("Ref 1234-5678", &[]), "Ref 1234-5678");
+    }
+}
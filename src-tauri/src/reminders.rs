@@ -0,0 +1,142 @@
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{activity, background, dnd, settings};
+
+// How often the scheduler looks for due reminders
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// Reminders missed while the app was closed still fire if they are at most this late
+const MISSED_GRACE_MINUTES: i64 = 30;
+
+// Serializes read-modify-write of the stored list between commands and the scheduler
+static REMINDERS_LOCK: Mutex<()> = Mutex::new(());
+// Disambiguates ids created in the same millisecond
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+// A notification scheduled locally, so it fires even while the backend is down
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: String,
+    // RFC 3339 time it is due
+    pub at: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl Reminder {
+    fn due(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.at).ok()
+    }
+}
+
+// Pending reminders, soonest first. Unparseable entries are skipped.
+pub fn pending<R: Runtime>(app: &AppHandle<R>) -> Vec<Reminder> {
+    let mut reminders: Vec<Reminder> = match settings::get(app, settings::REMINDERS) {
+        Some(Value::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|e| serde_json::from_value(e).ok())
+            .collect(),
+        _ => Vec::new(),
+    };
+    reminders.sort_by_key(|reminder| reminder.due());
+    reminders
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, reminders: &[Reminder]) -> Result<(), String> {
+    let reminders = serde_json::to_value(reminders).map_err(|e| e.to_string())?;
+    settings::set(app, settings::REMINDERS, reminders)
+}
+
+// Stores a reminder due at `at` (RFC 3339) and returns its id
+pub fn schedule<R: Runtime>(app: &AppHandle<R>, at: &str, title: &str, body: &str) -> Result<String, String> {
+    let due = DateTime::parse_from_rfc3339(at.trim())
+        .map_err(|e| format!("Invalid reminder time '{}': {}", at, e))?;
+    if due < Local::now() {
+        return Err(format!("Reminder time {} is in the past", at));
+    }
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Reminder title must not be empty".to_string());
+    }
+
+    let reminder = Reminder {
+        id: format!("{}-{}", activity::now_ms(), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+        at: due.to_rfc3339(),
+        title: title.to_string(),
+        body: body.trim().to_string(),
+    };
+    let _guard = REMINDERS_LOCK.lock().unwrap();
+    let mut reminders = pending(app);
+    reminders.push(reminder.clone());
+    save(app, &reminders)?;
+    log::info!("Scheduled reminder {} for {}", reminder.id, reminder.at);
+    Ok(reminder.id)
+}
+
+pub fn cancel<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<(), String> {
+    let _guard = REMINDERS_LOCK.lock().unwrap();
+    let mut reminders = pending(app);
+    let count = reminders.len();
+    reminders.retain(|reminder| reminder.id != id);
+    if reminders.len() == count {
+        return Err(format!("No pending reminder with id '{}'", id));
+    }
+    save(app, &reminders)
+}
+
+// Removes and returns the reminders that are due. Ones too late to be useful
+// (missed by more than the grace window) are dropped.
+fn take_due<R: Runtime>(app: &AppHandle<R>) -> Vec<Reminder> {
+    let _guard = REMINDERS_LOCK.lock().unwrap();
+    let now = Local::now();
+    let (due, later): (Vec<_>, Vec<_>) = pending(app)
+        .into_iter()
+        .partition(|reminder| reminder.due().is_none_or(|at| at <= now));
+    if due.is_empty() {
+        return due;
+    }
+    if let Err(e) = save(app, &later) {
+        log::warn!("Failed to save reminders: {}", e);
+    }
+
+    due.into_iter()
+        .filter(|reminder| {
+            let late = reminder.due().map(|at| (now.fixed_offset() - at).num_minutes());
+            let fire = late.is_some_and(|late| late <= MISSED_GRACE_MINUTES);
+            if !fire {
+                log::info!("Dropping reminder {}, missed at {}", reminder.id, reminder.at);
+            }
+            fire
+        })
+        .collect()
+}
+
+fn fire<R: Runtime>(app: &AppHandle<R>, reminder: &Reminder) {
+    let shown = dnd::allows_notification(app, false);
+    if shown {
+        let _ = app
+            .notification()
+            .builder()
+            .title(&reminder.title)
+            .body(&reminder.body)
+            .show();
+    }
+    log::info!("Reminder {} due{}", reminder.id, if shown { "" } else { " (held back by do not disturb)" });
+    let _ = app.emit("reminder-fired", serde_json::json!({ "reminder": reminder, "shown": shown }));
+}
+
+// Fires stored reminders when they come due, independent of the backend.
+// Reminders missed while the app was closed fire on the first check.
+pub fn start_scheduler<R: Runtime>(app: AppHandle<R>) {
+    background::spawn_loop(&app, "reminder scheduler", CHECK_INTERVAL, |app| {
+        for reminder in take_due(app) {
+            fire(app, &reminder);
+        }
+    });
+}
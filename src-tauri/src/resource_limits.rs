@@ -0,0 +1,216 @@
+use serde::Serialize;
+use tauri::{AppHandle, Runtime};
+
+use crate::settings;
+
+// Niceness for the backend on Unix; on Windows anything above 0 means below
+// normal priority, and 15 or more idle priority. 0 leaves it alone.
+pub const NICE_RANGE: std::ops::RangeInclusive<i64> = 0..=19;
+pub const DEFAULT_NICE: i64 = 5;
+// A smaller cap would stop the interpreter from loading its dependencies
+pub const MIN_MEMORY_LIMIT_MB: u64 = 256;
+pub const CPU_LIMIT_RANGE: std::ops::RangeInclusive<u64> = 1..=100;
+
+// Guardrails for the backend process so it can't make the desktop sluggish.
+// Memory and CPU are unlimited unless the user sets them.
+#[derive(Clone, Copy, Serialize)]
+pub struct ResourceLimits {
+    pub nice: i64,
+    pub memory_limit_mb: Option<u64>,
+    // Share of total CPU time; enforced on Windows only
+    pub cpu_limit_percent: Option<u64>,
+}
+
+pub fn configured<R: Runtime>(app: &AppHandle<R>) -> ResourceLimits {
+    ResourceLimits {
+        nice: settings::get(app, settings::BACKEND_NICE)
+            .and_then(|value| value.as_i64())
+            .filter(|nice| NICE_RANGE.contains(nice))
+            .unwrap_or(DEFAULT_NICE),
+        memory_limit_mb: settings::get_u64(app, settings::BACKEND_MEMORY_LIMIT_MB)
+            .filter(|mb| *mb >= MIN_MEMORY_LIMIT_MB),
+        cpu_limit_percent: settings::get_u64(app, settings::BACKEND_CPU_LIMIT_PERCENT)
+            .filter(|percent| CPU_LIMIT_RANGE.contains(percent)),
+    }
+}
+
+// Stores the limits for the next backend start. None restores the default
+// niceness, or removes the memory or CPU cap.
+pub fn set<R: Runtime>(
+    app: &AppHandle<R>,
+    nice: Option<i64>,
+    memory_limit_mb: Option<u64>,
+    cpu_limit_percent: Option<u64>,
+) -> Result<(), String> {
+    if nice.is_some_and(|nice| !NICE_RANGE.contains(&nice)) {
+        return Err(format!("Niceness must be {}-{}", NICE_RANGE.start(), NICE_RANGE.end()));
+    }
+    if memory_limit_mb.is_some_and(|mb| mb < MIN_MEMORY_LIMIT_MB) {
+        return Err(format!("The memory limit must be at least {} MB", MIN_MEMORY_LIMIT_MB));
+    }
+    if cpu_limit_percent.is_some_and(|percent| !CPU_LIMIT_RANGE.contains(&percent)) {
+        return Err(format!(
+            "The CPU limit must be {}-{}%",
+            CPU_LIMIT_RANGE.start(),
+            CPU_LIMIT_RANGE.end()
+        ));
+    }
+    for (key, value) in [
+        (settings::BACKEND_NICE, nice.map(serde_json::Value::from)),
+        (settings::BACKEND_MEMORY_LIMIT_MB, memory_limit_mb.map(serde_json::Value::from)),
+        (settings::BACKEND_CPU_LIMIT_PERCENT, cpu_limit_percent.map(serde_json::Value::from)),
+    ] {
+        match value {
+            Some(value) => settings::set(app, key, value)?,
+            None => settings::delete(app, key)?,
+        }
+    }
+    Ok(())
+}
+
+fn report(what: &str, limit: &str, result: Result<(), String>) {
+    match result {
+        Ok(()) => log::info!("Backend {} limited to {}", what, limit),
+        Err(e) => log::warn!("The OS rejected the backend {} limit ({}): {}", what, limit, e),
+    }
+}
+
+// Limits in force on a running backend. On Windows this owns the job object,
+// which is closed once the process is gone.
+#[derive(Default)]
+pub struct AppliedLimits {
+    // The job's HANDLE, kept as an integer so the process state stays Send
+    #[cfg(windows)]
+    job: Option<usize>,
+}
+
+#[cfg(windows)]
+impl Drop for AppliedLimits {
+    fn drop(&mut self) {
+        if let Some(job) = self.job.take() {
+            unsafe { windows_sys::Win32::Foundation::CloseHandle(job as windows_sys::Win32::Foundation::HANDLE) };
+        }
+    }
+}
+
+#[cfg(unix)]
+fn last_error() -> String {
+    std::io::Error::last_os_error().to_string()
+}
+
+// Applies the limits to a freshly spawned backend. Each one is best effort:
+// what the OS refuses is logged and skipped.
+#[cfg(unix)]
+pub fn apply(child: &std::process::Child, limits: ResourceLimits) -> AppliedLimits {
+    let pid = child.id();
+    if limits.nice > 0 {
+        let result =
+            unsafe { libc::setpriority(libc::PRIO_PROCESS as _, pid as libc::id_t, limits.nice as libc::c_int) };
+        report("priority", &format!("nice {}", limits.nice), if result == 0 { Ok(()) } else { Err(last_error()) });
+    }
+
+    if let Some(mb) = limits.memory_limit_mb {
+        #[cfg(target_os = "linux")]
+        {
+            let bytes = (mb * 1024 * 1024) as libc::rlim_t;
+            let limit = libc::rlimit {
+                rlim_cur: bytes,
+                rlim_max: bytes,
+            };
+            let result = unsafe { libc::prlimit(pid as libc::pid_t, libc::RLIMIT_AS, &limit, std::ptr::null_mut()) };
+            report("memory", &format!("{} MB", mb), if result == 0 { Ok(()) } else { Err(last_error()) });
+        }
+        // macOS doesn't enforce address space limits
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("A backend memory limit of {} MB is set, but this OS can't enforce it", mb);
+    }
+
+    if let Some(percent) = limits.cpu_limit_percent {
+        log::warn!(
+            "A backend CPU limit of {}% is set, but hard CPU caps are Windows only; relying on nice {}",
+            percent, limits.nice
+        );
+    }
+    AppliedLimits::default()
+}
+
+// Priority is set on the process itself; memory and CPU caps come from a job
+// object, which also ends the backend if the app dies without stopping it
+#[cfg(windows)]
+pub fn apply(child: &std::process::Child, limits: ResourceLimits) -> AppliedLimits {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectCpuRateControlInformation,
+        JobObjectExtendedLimitInformation, SetInformationJobObject, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+        JOBOBJECT_CPU_RATE_CONTROL_INFORMATION_0, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+    use windows_sys::Win32::System::Threading::{SetPriorityClass, BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS};
+
+    let checked = |ok: BOOL| if ok != 0 { Ok(()) } else { Err(std::io::Error::last_os_error().to_string()) };
+    let process = child.as_raw_handle() as HANDLE;
+
+    if limits.nice > 0 {
+        let (class, name) = if limits.nice >= 15 {
+            (IDLE_PRIORITY_CLASS, "idle")
+        } else {
+            (BELOW_NORMAL_PRIORITY_CLASS, "below normal")
+        };
+        report("priority", name, checked(unsafe { SetPriorityClass(process, class) }));
+    }
+
+    if limits.memory_limit_mb.is_none() && limits.cpu_limit_percent.is_none() {
+        return AppliedLimits::default();
+    }
+    let job = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+    if job.is_null() {
+        log::warn!("Failed to create a job object for the backend: {}", std::io::Error::last_os_error());
+        return AppliedLimits::default();
+    }
+
+    let mut extended: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    extended.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+    if let Some(mb) = limits.memory_limit_mb {
+        extended.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        extended.ProcessMemoryLimit = (mb * 1024 * 1024) as usize;
+    }
+    let result = checked(unsafe {
+        SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            std::ptr::from_ref(&extended).cast(),
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+    });
+    if let Some(mb) = limits.memory_limit_mb {
+        report("memory", &format!("{} MB", mb), result);
+    }
+
+    if let Some(percent) = limits.cpu_limit_percent {
+        // CpuRate is in hundredths of a percent
+        let rate = JOBOBJECT_CPU_RATE_CONTROL_INFORMATION {
+            ControlFlags: JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+            Anonymous: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION_0 {
+                CpuRate: (percent * 100) as u32,
+            },
+        };
+        let result = checked(unsafe {
+            SetInformationJobObject(
+                job,
+                JobObjectCpuRateControlInformation,
+                std::ptr::from_ref(&rate).cast(),
+                std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+            )
+        });
+        report("CPU", &format!("{}%", percent), result);
+    }
+
+    if let Err(e) = checked(unsafe { AssignProcessToJobObject(job, process) }) {
+        log::warn!("Failed to put the backend in its job object, limits not applied: {}", e);
+        unsafe { CloseHandle(job) };
+        return AppliedLimits::default();
+    }
+    AppliedLimits { job: Some(job as usize) }
+}
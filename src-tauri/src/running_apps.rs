@@ -0,0 +1,51 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::os_integration;
+
+// Enumerating windows costs a few hundred milliseconds on some platforms
+// (osascript on macOS), so a list this fresh is handed out again as-is
+const MAX_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize)]
+pub struct AppInfo {
+    pub name: String,
+    // Executable path, where the OS reveals it
+    pub path: Option<String>,
+    pub pid: u32,
+}
+
+// The last enumeration, refreshed on demand once it is older than MAX_AGE
+#[derive(Default)]
+pub struct RunningApps {
+    cached: Mutex<Option<(Instant, Vec<AppInfo>)>>,
+}
+
+impl RunningApps {
+    pub fn list(&self) -> Vec<AppInfo> {
+        let mut cached = self.cached.lock().unwrap();
+        if let Some((refreshed, apps)) = cached.as_ref() {
+            if refreshed.elapsed() < MAX_AGE {
+                return apps.clone();
+            }
+        }
+
+        let Some(found) = os_integration::running_apps() else {
+            log::debug!("Could not enumerate running applications");
+            return Vec::new();
+        };
+        let mut apps: Vec<AppInfo> = found
+            .into_iter()
+            .map(|app| AppInfo {
+                name: app.name,
+                path: app.path.map(|path| path.to_string_lossy().to_string()),
+                pid: app.pid,
+            })
+            .collect();
+        apps.sort_by_key(|app| app.name.to_lowercase());
+
+        *cached = Some((Instant::now(), apps.clone()));
+        apps
+    }
+}
@@ -0,0 +1,210 @@
+use serde_json::Value;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::{enrichers, os_integration, privacy};
+
+// All desktop-shell preferences live in a single store file in the app data dir
+const SETTINGS_FILE: &str = "settings.json";
+
+// Setting keys
+pub const TRACK_CLIPBOARD_CHANGES: &str = "track_clipboard_changes";
+pub const PAYLOAD_FIELDS: &str = "payload_fields";
+pub const STEAL_FOCUS_ON_SHOW: &str = "steal_focus_on_show";
+pub const LOW_BATTERY_PAUSE_PERCENT: &str = "low_battery_pause_percent";
+pub const DAILY_SUMMARY_TIME: &str = "daily_summary_time";
+pub const DAILY_SUMMARY_LAST_SENT: &str = "daily_summary_last_sent";
+pub const BACKEND_PORT: &str = "backend_port";
+pub const ACTIVITY_PATH: &str = "activity_path";
+pub const HEALTH_PATH: &str = "health_path";
+pub const IDLE_THRESHOLD_SECS: &str = "idle_threshold_secs";
+pub const TRACKING_INTERVAL_SECS: &str = "tracking_interval_secs";
+pub const TRACKING_ENABLED: &str = "tracking_enabled";
+pub const BLOCKED_APPS: &str = "blocked_apps";
+pub const PYTHON_PATH: &str = "python_path";
+pub const CAPTURE_URL_WHEN_PRIVACY_UNKNOWN: &str = "capture_url_when_privacy_unknown";
+pub const AUTOSTART: &str = "autostart";
+pub const ACTIVITY_HEARTBEAT_SECS: &str = "activity_heartbeat_secs";
+pub const BACKEND_NOTIFICATIONS: &str = "backend_notifications";
+pub const MIN_DWELL_MS: &str = "min_dwell_ms";
+pub const REDACT_TITLES: &str = "redact_titles";
+pub const TITLE_REDACTION_PATTERNS: &str = "title_redaction_patterns";
+pub const MAIN_WINDOW_STATE: &str = "main_window_state";
+pub const BACKEND_PROCESS: &str = "backend_process";
+pub const GLOBAL_SHORTCUT: &str = "global_shortcut";
+pub const CATEGORY_RULES: &str = "category_rules";
+pub const STRIP_URL_PARAMS: &str = "strip_url_params";
+pub const URL_PARAM_ALLOWLIST: &str = "url_param_allowlist";
+pub const SCREENSHOTS_ENABLED: &str = "screenshots_enabled";
+pub const ENRICHERS: &str = "enrichers";
+pub const DND: &str = "dnd";
+pub const DND_UNTIL_MS: &str = "dnd_until_ms";
+pub const DND_ALLOW_CRITICAL: &str = "dnd_allow_critical";
+pub const REMINDERS: &str = "reminders";
+pub const PROXY_URL: &str = "proxy_url";
+pub const FOCUS_SESSION: &str = "focus_session";
+pub const CAPTURE_URLS: &str = "capture_urls";
+pub const MANUAL_ACTIVITY: &str = "manual_activity";
+pub const MANUAL_SUPPRESSES_TRACKING: &str = "manual_suppresses_tracking";
+pub const BACKEND_NICE: &str = "backend_nice";
+pub const BACKEND_MEMORY_LIMIT_MB: &str = "backend_memory_limit_mb";
+pub const BACKEND_CPU_LIMIT_PERCENT: &str = "backend_cpu_limit_percent";
+pub const SNOOZE_UNTIL_MS: &str = "snooze_until_ms";
+
+// Activity payload fields that are always sent
+pub const CORE_PAYLOAD_FIELDS: [&str; 3] = ["app_name", "captured_at", "seq"];
+// Activity payload fields the user can switch off; all are enabled by default
+pub const OPTIONAL_PAYLOAD_FIELDS: [&str; 8] = [
+    "app_id",
+    "window_title",
+    "url",
+    "clipboard_changes",
+    "category",
+    "monitor",
+    "exe_path",
+    "bundle_id",
+];
+
+pub fn get<R: Runtime>(app: &AppHandle<R>, key: &str) -> Option<Value> {
+    app.store(SETTINGS_FILE).ok()?.get(key)
+}
+
+pub fn get_bool<R: Runtime>(app: &AppHandle<R>, key: &str, default: bool) -> bool {
+    get(app, key).and_then(|v| v.as_bool()).unwrap_or(default)
+}
+
+pub fn get_u64<R: Runtime>(app: &AppHandle<R>, key: &str) -> Option<u64> {
+    get(app, key).and_then(|v| v.as_u64())
+}
+
+pub fn set<R: Runtime>(app: &AppHandle<R>, key: &str, value: impl Into<Value>) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_FILE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    store.set(key, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+// Returns the enabled optional payload fields, ignoring unknown names
+pub fn payload_fields<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    match get(app, PAYLOAD_FIELDS) {
+        Some(Value::Array(fields)) => fields
+            .iter()
+            .filter_map(|f| f.as_str())
+            .filter(|f| OPTIONAL_PAYLOAD_FIELDS.contains(f))
+            .map(str::to_string)
+            .collect(),
+        _ => OPTIONAL_PAYLOAD_FIELDS.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+// App-name substrings that must never be tracked, stored lowercased
+pub fn blocked_apps<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    match get(app, BLOCKED_APPS) {
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .filter_map(|e| e.as_str())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// User rules for classifying activity, checked before the built-in ones.
+// Unparseable entries are skipped.
+pub fn category_rules<R: Runtime>(app: &AppHandle<R>) -> Vec<os_integration::CategoryRule> {
+    match get(app, CATEGORY_RULES) {
+        Some(Value::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|e| serde_json::from_value(e).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Scripts that add fields to activity records. Unparseable entries are skipped.
+pub fn enrichers<R: Runtime>(app: &AppHandle<R>) -> Vec<enrichers::Enricher> {
+    match get(app, ENRICHERS) {
+        Some(Value::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|e| serde_json::from_value(e).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Query parameters kept when URLs are stripped
+pub fn url_param_allowlist<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    match get(app, URL_PARAM_ALLOWLIST) {
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .filter_map(|e| e.as_str())
+            .map(str::to_string)
+            .collect(),
+        _ => privacy::DEFAULT_URL_PARAM_ALLOWLIST.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+// Extra regexes whose matches are redacted from window titles
+pub fn title_redaction_patterns<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    match get(app, TITLE_REDACTION_PATTERNS) {
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .filter_map(|e| e.as_str())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Case-insensitive substring match against the blocklist
+pub fn is_app_blocked<R: Runtime>(app: &AppHandle<R>, app_name: &str) -> bool {
+    let app_name = app_name.to_lowercase();
+    blocked_apps(app).iter().any(|entry| app_name.contains(entry.as_str()))
+}
+
+// Like set, but leaves the write to the store's debounced auto-save, for values
+// that change many times a second such as window geometry while dragging
+pub fn set_deferred<R: Runtime>(app: &AppHandle<R>, key: &str, value: impl Into<Value>) {
+    if let Ok(store) = app.store(SETTINGS_FILE) {
+        store.set(key, value);
+    }
+}
+
+// Bookkeeping, user data and in-progress sessions that survive a settings
+// reset; every other key is a preference
+const RESET_KEEP: [&str; 5] = [
+    BACKEND_PROCESS,
+    DAILY_SUMMARY_LAST_SENT,
+    REMINDERS,
+    FOCUS_SESSION,
+    MANUAL_ACTIVITY,
+];
+
+// Removes every preference so the defaults apply again. Activity data lives in
+// the backend and is untouched.
+pub fn reset<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_FILE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    for key in store.keys() {
+        if !RESET_KEEP.contains(&key.as_str()) {
+            store.delete(&key);
+        }
+    }
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+pub fn delete<R: Runtime>(app: &AppHandle<R>, key: &str) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_FILE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    store.delete(key);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
@@ -0,0 +1,59 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::{backend, deep_link, main_window};
+
+// The instance lock listens on the port right above the backend's, so dev
+// instances configured with different backend ports don't block each other
+fn lock_port() -> u16 {
+    backend::configured_port().wrapping_add(1)
+}
+
+// Claims the single-instance lock. Returns false if another instance already
+// holds it, after forwarding this launch's arguments (e.g. a deep link) to it.
+pub fn acquire<R: Runtime>(app: &AppHandle<R>) -> bool {
+    match TcpListener::bind((Ipv4Addr::LOCALHOST, lock_port())) {
+        Ok(listener) => {
+            listen(app.clone(), listener);
+            true
+        }
+        Err(_) => {
+            forward_args();
+            false
+        }
+    }
+}
+
+fn forward_args() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Ok(mut stream) = TcpStream::connect_timeout(
+        &(Ipv4Addr::LOCALHOST, lock_port()).into(),
+        Duration::from_secs(1),
+    ) else {
+        log::warn!("Another instance holds the instance lock but isn't answering");
+        return;
+    };
+    let _ = stream.write_all(args.join("\n").as_bytes());
+}
+
+// Handles later launches: brings the window forward and passes on deep links
+fn listen<R: Runtime>(app: AppHandle<R>, listener: TcpListener) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut args = String::new();
+            let _ = stream.take(64 * 1024).read_to_string(&mut args);
+            log::info!("Second launch detected, showing the existing window");
+
+            let urls: Vec<url::Url> = args
+                .lines()
+                .filter_map(|arg| url::Url::parse(arg).ok())
+                .filter(|url| deep_link::route_for(url).is_some())
+                .collect();
+            if deep_link::handle(&app, urls).is_none() {
+                main_window::show(&app);
+            }
+        }
+    });
+}
@@ -0,0 +1,113 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::{activity, background, settings, tracking, tray};
+
+pub const SNOOZE_MINUTES_RANGE: std::ops::RangeInclusive<u64> = 1..=24 * 60;
+// How often the timer checks whether the snooze is over
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Serializes snoozing and resuming between commands and the timer
+static SNOOZE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize)]
+pub struct SnoozeStatus {
+    pub snoozed: bool,
+    pub until_ms: Option<u64>,
+    pub remaining_ms: u64,
+}
+
+// When the snooze ends. Only counts while tracking is actually paused: resuming
+// by hand ends the snooze too.
+fn until_ms<R: Runtime>(app: &AppHandle<R>) -> Option<u64> {
+    settings::get_u64(app, settings::SNOOZE_UNTIL_MS).filter(|_| !app.state::<tracking::TrackingEnabled>().get())
+}
+
+pub fn status<R: Runtime>(app: &AppHandle<R>) -> SnoozeStatus {
+    let until_ms = until_ms(app);
+    SnoozeStatus {
+        snoozed: until_ms.is_some(),
+        until_ms,
+        remaining_ms: until_ms.map_or(0, |until| until.saturating_sub(activity::now_ms())),
+    }
+}
+
+fn minutes_left(until: u64) -> u64 {
+    until.saturating_sub(activity::now_ms()).div_ceil(60_000)
+}
+
+// What the tray tooltip shows while snoozed
+pub fn label<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    until_ms(app).map(|until| format!("Tracking snoozed, resumes in {} min", minutes_left(until)))
+}
+
+// Pauses tracking for `minutes`, after which it resumes by itself. The end time
+// is stored, so a restart in between stays paused until then.
+pub fn snooze<R: Runtime>(app: &AppHandle<R>, minutes: u64) -> Result<SnoozeStatus, String> {
+    if !SNOOZE_MINUTES_RANGE.contains(&minutes) {
+        return Err(format!(
+            "Tracking can be snoozed for {}-{} minutes, got {}",
+            SNOOZE_MINUTES_RANGE.start(),
+            SNOOZE_MINUTES_RANGE.end(),
+            minutes
+        ));
+    }
+    {
+        let _guard = SNOOZE_LOCK.lock().unwrap();
+        settings::set(app, settings::SNOOZE_UNTIL_MS, activity::now_ms() + minutes * 60_000)?;
+        tracking::set_enabled(app, false)?;
+    }
+    log::info!("Tracking snoozed for {} minutes", minutes);
+    let status = status(app);
+    let _ = app.emit("snooze-changed", &status);
+    check(app);
+    Ok(status)
+}
+
+// Ends the snooze early and resumes tracking
+pub fn cancel<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let _guard = SNOOZE_LOCK.lock().unwrap();
+    if until_ms(app).is_none() {
+        return Err("Tracking isn't snoozed".to_string());
+    }
+    log::info!("Snooze cancelled");
+    resume(app)
+}
+
+fn resume<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    settings::delete(app, settings::SNOOZE_UNTIL_MS)?;
+    tracking::set_enabled(app, true)?;
+    let _ = app.emit("snooze-changed", &status(app));
+    let _ = app.emit("tracking-resumed", serde_json::json!({ "reason": "snooze" }));
+    Ok(())
+}
+
+// Resumes tracking once the snooze is over, including one that ran out while
+// the app was closed, and keeps the tray's time left current
+fn check<R: Runtime>(app: &AppHandle<R>) {
+    let _guard = SNOOZE_LOCK.lock().unwrap();
+    if settings::get_u64(app, settings::SNOOZE_UNTIL_MS).is_none() {
+        return;
+    }
+    let Some(until) = until_ms(app) else {
+        log::info!("Tracking was resumed by hand, ending the snooze");
+        let _ = settings::delete(app, settings::SNOOZE_UNTIL_MS);
+        let _ = app.emit("snooze-changed", &status(app));
+        return;
+    };
+    if activity::now_ms() < until {
+        tray::sync_snooze_item(app, minutes_left(until));
+        return;
+    }
+    log::info!("Snooze over, resuming tracking");
+    if let Err(e) = resume(app) {
+        log::warn!("Failed to resume tracking after the snooze: {}", e);
+    }
+}
+
+// Runs the snooze timer, independent of the frontend
+pub fn start_timer<R: Runtime>(app: AppHandle<R>) {
+    background::spawn_loop(&app, "snooze timer", CHECK_INTERVAL, check);
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::activity::ActivitySnapshot;
+use crate::os_integration::{self, CpuTimes};
+
+#[derive(Serialize)]
+pub struct SystemLoad {
+    // Overall CPU usage since the previous sample; None on the first call
+    pub cpu_percent: Option<f32>,
+    pub memory_total_bytes: Option<u64>,
+    pub memory_used_bytes: Option<u64>,
+    // Resident memory of the desktop shell itself
+    pub process_memory_bytes: Option<u64>,
+}
+
+// What get_system_stats returns: the focused window plus machine load
+#[derive(Serialize)]
+pub struct SystemStats {
+    #[serde(flatten)]
+    pub activity: ActivitySnapshot,
+    #[serde(flatten)]
+    pub load: SystemLoad,
+    // Seconds since the last keyboard/mouse input; left out where the OS can't tell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_seconds: Option<u64>,
+}
+
+// Keeps the previous CPU counters so usage is measured between calls
+// rather than averaged since boot
+#[derive(Default)]
+pub struct CpuSampler {
+    last: Mutex<Option<CpuTimes>>,
+}
+
+impl CpuSampler {
+    fn cpu_percent(&self) -> Option<f32> {
+        let Some(now) = os_integration::cpu_times() else {
+            return os_integration::cpu_usage_estimate();
+        };
+        let previous = self.last.lock().unwrap().replace(now)?;
+
+        let total = now.total.saturating_sub(previous.total);
+        if total == 0 {
+            return None;
+        }
+        let busy = now.busy.saturating_sub(previous.busy);
+        Some(busy as f32 / total as f32 * 100.0)
+    }
+
+    pub fn sample(&self) -> SystemLoad {
+        let memory = os_integration::memory_info();
+        SystemLoad {
+            cpu_percent: self.cpu_percent(),
+            memory_total_bytes: memory.as_ref().map(|m| m.total_bytes),
+            memory_used_bytes: memory.as_ref().map(|m| m.used_bytes),
+            process_memory_bytes: os_integration::process_memory_bytes(),
+        }
+    }
+}
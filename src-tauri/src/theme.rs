@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::os_integration;
+
+// Linux doesn't reliably report theme changes to the window, so the desktop
+// setting is polled this often instead
+#[cfg(target_os = "linux")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Last theme reported to the frontend, so each change is emitted once
+static LAST_THEME: Mutex<Option<&'static str>> = Mutex::new(None);
+
+fn name(dark: bool) -> &'static str {
+    if dark {
+        "dark"
+    } else {
+        "light"
+    }
+}
+
+// The window's theme as Tauri sees it, or None without a main window
+fn window_prefers_dark<R: Runtime>(app: &AppHandle<R>) -> Option<bool> {
+    let theme = app.get_webview_window("main")?.theme().ok()?;
+    Some(theme == tauri::Theme::Dark)
+}
+
+// "dark" or "light". Tauri's window theme follows the OS on macOS and Windows;
+// on Linux the desktop's color-scheme setting is the more reliable source.
+// Defaults to light when neither is known.
+pub fn current<R: Runtime>(app: &AppHandle<R>) -> &'static str {
+    let dark = if cfg!(target_os = "linux") {
+        os_integration::prefers_dark_theme().or_else(|| window_prefers_dark(app))
+    } else {
+        window_prefers_dark(app).or_else(os_integration::prefers_dark_theme)
+    };
+    name(dark.unwrap_or(false))
+}
+
+// Emits "theme-changed" if `theme` differs from what was last reported
+pub fn update<R: Runtime>(app: &AppHandle<R>, theme: &'static str) {
+    let mut last = LAST_THEME.lock().unwrap();
+    if *last == Some(theme) {
+        return;
+    }
+    let first = last.is_none();
+    *last = Some(theme);
+    drop(last);
+    if !first {
+        log::info!("System theme changed to {}", theme);
+        let _ = app.emit("theme-changed", theme);
+    }
+}
+
+// Called for the window's ThemeChanged event
+pub fn window_theme_changed<R: Runtime>(app: &AppHandle<R>, theme: &tauri::Theme) {
+    update(app, name(*theme == tauri::Theme::Dark));
+}
+
+// Records the starting theme and, where the window isn't told about changes,
+// watches for them
+pub fn start_monitor<R: Runtime>(app: AppHandle<R>) {
+    update(&app, current(&app));
+    #[cfg(target_os = "linux")]
+    crate::background::spawn_loop(&app, "theme monitor", POLL_INTERVAL, |app| update(app, current(app)));
+}
@@ -0,0 +1,1117 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::{Interval, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    activity, activity_queue, backend, background, dnd, enrichers, focus, manual_activity, os_integration, privacy,
+    settings, snooze, tray, usage,
+};
+
+// The loop must only ever run once, even if the backend is (re)started later
+static TRACKING_STARTED: AtomicBool = AtomicBool::new(false);
+
+// Set by the battery monitor while tracking is paused to save power
+static BATTERY_THROTTLED: AtomicBool = AtomicBool::new(false);
+
+// Sequence number for activity records, so the backend can order and dedupe them
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+// Set by the lock monitor while the screen is locked. Kept apart from
+// TrackingEnabled so unlocking never overrides a manual pause.
+static SCREEN_LOCKED: AtomicBool = AtomicBool::new(false);
+
+// Whether the user has tracking switched on. Pausing skips window capture and
+// pushes entirely; the state is persisted so it survives restarts.
+pub struct TrackingEnabled(AtomicBool);
+
+impl TrackingEnabled {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        Self(AtomicBool::new(settings::get_bool(app, settings::TRACKING_ENABLED, true)))
+    }
+
+    pub fn get(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Messages that drive the tracking loop from commands and the tray
+pub enum Control {
+    Pause,
+    Resume,
+    SetInterval(u64),
+}
+
+// The sending half of the loop's control channel, set once the loop starts
+#[derive(Default)]
+pub struct TrackingControl {
+    sender: Mutex<Option<UnboundedSender<Control>>>,
+}
+
+// Passes a control message to the loop. Dropped if the loop isn't running yet;
+// it reads the persisted settings when it starts.
+pub fn send_control<R: Runtime>(app: &AppHandle<R>, message: Control) {
+    if let Some(control) = app.try_state::<TrackingControl>() {
+        if let Some(sender) = control.sender.lock().unwrap().as_ref() {
+            let _ = sender.send(message);
+        }
+    }
+}
+
+// Ticks every interval_secs. A slow iteration delays the next tick rather than
+// causing a burst of catch-up ticks.
+fn ticker(interval_secs: u64) -> Interval {
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker
+}
+
+// The loop's schedule and pause state, driven by control messages between ticks
+struct LoopControl {
+    receiver: UnboundedReceiver<Control>,
+    shutdown: CancellationToken,
+    paused: bool,
+    interval_secs: u64,
+    ticks: Interval,
+}
+
+impl LoopControl {
+    fn new(
+        receiver: UnboundedReceiver<Control>,
+        shutdown: CancellationToken,
+        paused: bool,
+        interval_secs: u64,
+    ) -> Self {
+        LoopControl {
+            receiver,
+            shutdown,
+            paused,
+            interval_secs,
+            ticks: ticker(interval_secs),
+        }
+    }
+
+    // Waits for the next tick, applying control messages as they arrive. A
+    // message brings the tick forward so the change takes effect right away.
+    // False once shutdown has started.
+    async fn next_tick(&mut self) -> bool {
+        loop {
+            let message = tokio::select! {
+                _ = self.shutdown.cancelled() => return false,
+                Some(message) = self.receiver.recv() => message,
+                _ = self.ticks.tick() => return true,
+            };
+            self.apply(message);
+        }
+    }
+
+    fn apply(&mut self, message: Control) {
+        match message {
+            Control::Pause => self.paused = true,
+            Control::Resume => self.paused = false,
+            Control::SetInterval(secs) => {
+                self.interval_secs = secs;
+                self.ticks = ticker(secs);
+            }
+        }
+        self.ticks.reset_immediately();
+    }
+}
+
+// Pauses or resumes tracking, persisting the choice and keeping the tray in sync
+pub fn set_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    apply_enabled(app, enabled);
+    settings::set(app, settings::TRACKING_ENABLED, enabled)
+}
+
+fn apply_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) {
+    app.state::<TrackingEnabled>().0.store(enabled, Ordering::Relaxed);
+    send_control(app, if enabled { Control::Resume } else { Control::Pause });
+    tray::sync_tracking_item(app, enabled);
+    let _ = app.emit("tracking-enabled-changed", enabled);
+}
+
+// Brings the running loop in line with the persisted settings, e.g. after
+// they were reset
+pub fn reload_settings<R: Runtime>(app: &AppHandle<R>) {
+    apply_enabled(app, settings::get_bool(app, settings::TRACKING_ENABLED, true));
+    send_control(app, Control::SetInterval(tracking_interval_secs(app)));
+}
+
+// How often the battery monitor samples the battery
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// How often the lock monitor checks the screen
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+// Default and allowed range for tracking_interval_secs. Very short intervals
+// keep the CPU awake and cost noticeable battery life.
+pub const DEFAULT_TRACKING_INTERVAL_SECS: u64 = 5;
+pub const TRACKING_INTERVAL_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+
+// Default for idle_threshold_secs: no input for this long counts as idle
+pub const DEFAULT_IDLE_THRESHOLD_SECS: u64 = 300;
+
+// Default and allowed range for activity_heartbeat_secs: how long a segment
+// for an unchanged window may run before it is sent anyway
+pub const DEFAULT_HEARTBEAT_SECS: u64 = 60;
+pub const HEARTBEAT_RANGE: std::ops::RangeInclusive<u64> = 10..=3600;
+
+// Default and allowed range for min_dwell_ms: how long a newly focused window
+// must keep focus before it is recorded. 0 records every window.
+pub const DEFAULT_MIN_DWELL_MS: u64 = 1500;
+pub const MIN_DWELL_RANGE: std::ops::RangeInclusive<u64> = 0..=60_000;
+
+// Why a segment ended, sent with its record as end_reason
+#[derive(Clone, Copy)]
+pub enum EndReason {
+    FocusChange,
+    Heartbeat,
+    Paused,
+    Idle,
+    Locked,
+    Blocked,
+    Unreadable,
+    Shutdown,
+    Interrupted,
+    Manual,
+}
+
+impl EndReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            EndReason::FocusChange => "focus_change",
+            EndReason::Heartbeat => "heartbeat",
+            EndReason::Paused => "paused",
+            EndReason::Idle => "idle",
+            EndReason::Locked => "locked",
+            EndReason::Blocked => "blocked",
+            EndReason::Unreadable => "unreadable",
+            EndReason::Shutdown => "shutdown",
+            EndReason::Interrupted => "interrupted",
+            EndReason::Manual => "manual",
+        }
+    }
+}
+
+// A stretch of time on one window, sent as a single record when focus moves
+// on or the heartbeat interval passes
+struct Segment {
+    payload: serde_json::Value,
+    start: chrono::DateTime<chrono::Local>,
+    started: Instant,
+}
+
+impl Segment {
+    // A segment for a window that has had focus since `since`
+    fn new(payload: serde_json::Value, since: Instant) -> Self {
+        let start = chrono::Local::now() - since.elapsed();
+        Self {
+            payload,
+            start,
+            started: since,
+        }
+    }
+
+    // Whether a capture belongs to this segment. Only what identifies the
+    // window (and the focus session or manual activity) counts; sampled fields like
+    // clipboard_changes don't split segments.
+    fn matches(&self, payload: &serde_json::Value) -> bool {
+        ["app_name", "window_title", "url", "focus_session_id", "manual_activity"]
+            .iter()
+            .all(|key| self.payload.get(key) == payload.get(key))
+    }
+
+    // Whether the window is the one this segment is for. The title only counts
+    // when it is part of the payload.
+    fn is_for(&self, app_name: &str, title: &str) -> bool {
+        self.payload["app_name"] == app_name
+            && self.payload.get("window_title").is_none_or(|t| t == title)
+    }
+
+    fn into_record(self, ended: Instant, reason: EndReason) -> serde_json::Value {
+        let duration = ended.saturating_duration_since(self.started);
+        let mut record = self.payload;
+        record["start"] = self.start.to_rfc3339().into();
+        record["end"] = (self.start + duration).to_rfc3339().into();
+        record["duration_secs"] = duration.as_secs_f64().into();
+        record["end_reason"] = reason.as_str().into();
+        stamp(&mut record);
+        record
+    }
+}
+
+// The segment currently being accumulated, in app state so it can be flushed
+// from outside the loop at shutdown
+#[derive(Default)]
+pub struct OpenSegment(Mutex<Option<Segment>>);
+
+impl OpenSegment {
+    // Folds a capture into the open segment. Returns a finished record to send
+    // when the window changed (at `focused_since`) or the heartbeat is due.
+    fn observe(
+        &self,
+        payload: serde_json::Value,
+        heartbeat: Duration,
+        focused_since: Instant,
+    ) -> Option<serde_json::Value> {
+        let mut open = self.0.lock().unwrap();
+        match open.as_mut() {
+            Some(segment) if segment.matches(&payload) => {
+                // Same window, keep the freshest sampled fields. Fields only the
+                // first capture has, such as enrichments, carry over.
+                let mut payload = payload;
+                if let (Some(fresh), Some(old)) = (payload.as_object_mut(), segment.payload.as_object()) {
+                    for (key, value) in old {
+                        fresh.entry(key.as_str()).or_insert_with(|| value.clone());
+                    }
+                }
+                segment.payload = payload.clone();
+                if segment.started.elapsed() < heartbeat {
+                    return None;
+                }
+                let now = Instant::now();
+                open.replace(Segment::new(payload, now))
+                    .map(|old| old.into_record(now, EndReason::Heartbeat))
+            }
+            _ => open
+                .replace(Segment::new(payload, focused_since))
+                .map(|old| old.into_record(focused_since, EndReason::FocusChange)),
+        }
+    }
+
+    // Whether a capture would extend the open segment rather than start a new one
+    fn continues(&self, payload: &serde_json::Value) -> bool {
+        self.0.lock().unwrap().as_ref().is_some_and(|segment| segment.matches(payload))
+    }
+
+    fn is_current(&self, app_name: &str, title: &str) -> bool {
+        self.0.lock().unwrap().as_ref().is_some_and(|segment| segment.is_for(app_name, title))
+    }
+
+    fn take(&self, reason: EndReason) -> Option<serde_json::Value> {
+        self.take_at(Instant::now(), reason)
+    }
+
+    // Closes the open segment as of `ended`
+    fn take_at(&self, ended: Instant, reason: EndReason) -> Option<serde_json::Value> {
+        self.0.lock().unwrap().take().map(|segment| segment.into_record(ended, reason))
+    }
+
+    // The open segment as a record ending now, without closing it
+    pub fn preview(&self) -> Option<serde_json::Value> {
+        self.0.lock().unwrap().as_ref().map(|segment| {
+            let mut record = segment.payload.clone();
+            record["start"] = segment.start.to_rfc3339().into();
+            record["duration_secs"] = segment.started.elapsed().as_secs_f64().into();
+            record
+        })
+    }
+}
+
+// Counts a finished segment towards today's local usage summary
+pub fn tally<R: Runtime>(app: &AppHandle<R>, record: &serde_json::Value) {
+    if let Some(usage) = app.try_state::<usage::UsageSummary>() {
+        usage.add(record);
+    }
+}
+
+// Sends whatever segment is open, e.g. when the screen locks
+pub fn flush_segment<R: Runtime>(app: &AppHandle<R>, reason: EndReason) {
+    if let Some(record) = app.try_state::<OpenSegment>().and_then(|open| open.take(reason)) {
+        tally(app, &record);
+        activity_queue::post_blocking(app, record);
+    }
+}
+
+// Closes the open segment as the app exits. The backend may already be going
+// away, so it gets one quick attempt; otherwise the record is kept for the
+// next launch.
+pub fn end_session<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(record) = app.try_state::<OpenSegment>().and_then(|open| open.take(EndReason::Shutdown)) {
+        tally(app, &record);
+        activity_queue::post_on_exit(app, record);
+    }
+}
+
+// flush_segment for the tracking loop itself
+async fn end_segment<R: Runtime>(app: &AppHandle<R>, reason: EndReason) {
+    end_segment_at(app, Instant::now(), reason).await;
+}
+
+async fn end_segment_at<R: Runtime>(app: &AppHandle<R>, ended: Instant, reason: EndReason) {
+    let record = app.state::<OpenSegment>().take_at(ended, reason);
+    if let Some(record) = record {
+        tally(app, &record);
+        activity_queue::post(app, record).await;
+    }
+}
+
+pub fn min_dwell_ms<R: Runtime>(app: &AppHandle<R>) -> u64 {
+    settings::get_u64(app, settings::MIN_DWELL_MS)
+        .filter(|ms| MIN_DWELL_RANGE.contains(ms))
+        .unwrap_or(DEFAULT_MIN_DWELL_MS)
+}
+
+pub fn heartbeat_secs<R: Runtime>(app: &AppHandle<R>) -> u64 {
+    settings::get_u64(app, settings::ACTIVITY_HEARTBEAT_SECS)
+        .filter(|secs| HEARTBEAT_RANGE.contains(secs))
+        .unwrap_or(DEFAULT_HEARTBEAT_SECS)
+}
+
+// Wall-clock and monotonic time disagreeing by more than this between ticks
+// means the clock was changed or the machine slept. Ticks running this much
+// later than scheduled mean the same where the monotonic clock counts sleep.
+const CLOCK_JUMP_TOLERANCE: Duration = Duration::from_secs(60);
+
+// The previous tick's monotonic and wall-clock time. Durations are measured
+// on the monotonic clock, so a jump in wall time can't make them negative,
+// but the segment spanning a jump no longer describes real time.
+#[derive(Default)]
+struct ClockCheck {
+    last: Option<(Instant, chrono::DateTime<chrono::Local>)>,
+}
+
+impl ClockCheck {
+    // Records this tick; returns the previous tick's time if time jumped since then
+    fn jumped(
+        &mut self,
+        now: Instant,
+        wall: chrono::DateTime<chrono::Local>,
+        expected_gap: Duration,
+    ) -> Option<Instant> {
+        let (last, last_wall) = self.last.replace((now, wall))?;
+        clock_jumped((last, last_wall), (now, wall), expected_gap).then_some(last)
+    }
+}
+
+// Whether time jumped between two ticks: wall time moved differently from
+// monotonic time, or the tick came much later than `expected_gap`
+fn clock_jumped(
+    (last, last_wall): (Instant, chrono::DateTime<chrono::Local>),
+    (now, wall): (Instant, chrono::DateTime<chrono::Local>),
+    expected_gap: Duration,
+) -> bool {
+    let elapsed = now.saturating_duration_since(last);
+    let wall_ms = (wall - last_wall).num_milliseconds();
+    let drift_ms = wall_ms.abs_diff(elapsed.as_millis() as i64);
+    let stalled = elapsed > expected_gap + CLOCK_JUMP_TOLERANCE;
+    drift_ms > CLOCK_JUMP_TOLERANCE.as_millis() as u64 || stalled
+}
+
+// Weight of the newest sample in the overhead moving averages
+const OVERHEAD_EMA_ALPHA: f64 = 0.1;
+
+// Moving averages of what one loop iteration costs, in milliseconds
+#[derive(Clone, Default, Serialize)]
+pub struct OverheadStats {
+    pub iterations: u64,
+    pub active_window_ms: f64,
+    pub browser_url_ms: f64,
+    pub send_ms: f64,
+    pub iteration_ms: f64,
+    // Share of one core the loop's own work takes, from thread CPU time.
+    // Waiting on osascript, UI Automation replies or the backend doesn't count.
+    pub cpu_share: f64,
+}
+
+// Self-measurement of the tracking loop so users can see what it costs
+#[derive(Default)]
+pub struct TrackingOverhead {
+    stats: Mutex<OverheadStats>,
+}
+
+struct OverheadSample {
+    active_window: Duration,
+    browser_url: Duration,
+    send: Duration,
+    busy: Duration,
+    cpu: Duration,
+    wall: Duration,
+}
+
+fn ema(current: f64, sample: f64, first: bool) -> f64 {
+    if first {
+        sample
+    } else {
+        current + OVERHEAD_EMA_ALPHA * (sample - current)
+    }
+}
+
+impl TrackingOverhead {
+    fn record(&self, sample: OverheadSample) {
+        let mut stats = self.stats.lock().unwrap();
+        let first = stats.iterations == 0;
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+        stats.active_window_ms = ema(stats.active_window_ms, ms(sample.active_window), first);
+        stats.browser_url_ms = ema(stats.browser_url_ms, ms(sample.browser_url), first);
+        stats.send_ms = ema(stats.send_ms, ms(sample.send), first);
+        stats.iteration_ms = ema(stats.iteration_ms, ms(sample.busy), first);
+        if !sample.wall.is_zero() {
+            let share = sample.cpu.as_secs_f64() / sample.wall.as_secs_f64();
+            stats.cpu_share = ema(stats.cpu_share, share, first);
+        }
+        stats.iterations += 1;
+    }
+
+    pub fn snapshot(&self) -> OverheadStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.stats.lock().unwrap() = OverheadStats::default();
+    }
+}
+
+// Runs blocking work off the async workers and adds the CPU time it used to
+// `cpu`. Only these sections are measured, since between awaits the loop can
+// move to another worker thread; the async parts mostly wait on HTTP anyway.
+fn measured<T>(cpu: &mut Duration, work: impl FnOnce() -> T) -> T {
+    tokio::task::block_in_place(|| {
+        let start = os_integration::thread_cpu_time();
+        let result = work();
+        if let (Some(start), Some(end)) = (start, os_integration::thread_cpu_time()) {
+            *cpu += end.saturating_sub(start);
+        }
+        result
+    })
+}
+
+// Per-loop caches for building payloads
+#[derive(Default)]
+struct CaptureState {
+    clipboard_monitor: os_integration::ClipboardMonitor,
+    // Resolving a bundle id spawns osascript, so cache it per process
+    identity_cache: Option<(u64, os_integration::AppIdentity)>,
+}
+
+// What has focus, as far as tracking is concerned
+enum Focus {
+    Window(active_win_pos_rs::ActiveWindow),
+    // An app on the blocklist; nothing about it is recorded
+    Blocked,
+    Nothing,
+}
+
+impl Focus {
+    fn into_window(self) -> Option<active_win_pos_rs::ActiveWindow> {
+        match self {
+            Focus::Window(window) => Some(window),
+            Focus::Blocked | Focus::Nothing => None,
+        }
+    }
+}
+
+// The focused window, unless it is blocked. Titles are redacted here so nothing
+// downstream sees the raw text.
+fn focused_window<R: Runtime>(app: &AppHandle<R>) -> Focus {
+    match active_win_pos_rs::get_active_window() {
+        Ok(window) if settings::is_app_blocked(app, &window.app_name) => Focus::Blocked,
+        Ok(mut window) => {
+            window.title = privacy::redact_title(app, &window.title);
+            Focus::Window(window)
+        }
+        Err(_) => Focus::Nothing,
+    }
+}
+
+// What is recorded while the focused window can't be read. Identical every
+// time, so consecutive reads fold into one segment.
+fn unknown_payload() -> serde_json::Value {
+    serde_json::json!({
+        "app_name": "Unknown",
+        "window_title": "",
+        "status": "unknown"
+    })
+}
+
+// Consecutive failed window reads before tracking concludes it lacks permission
+const PERMISSION_FAILURE_LIMIT: u32 = 10;
+// How often window access is retried while it is known to be denied
+const PERMISSION_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+// Spots a missing Screen Recording permission on macOS, where window reads then
+// fail or come back without titles. A single untitled app isn't conclusive;
+// some windows simply have no title.
+#[derive(Default)]
+struct PermissionWatch {
+    failures: u32,
+    // Apps seen without a title during the current run of failures
+    untitled_apps: HashSet<String>,
+    missing: bool,
+}
+
+impl PermissionWatch {
+    // Records one window read. Returns whether permission is believed missing.
+    fn observe<R: Runtime>(&mut self, app: &AppHandle<R>, focus: &Focus) -> bool {
+        if !cfg!(target_os = "macos") {
+            return false;
+        }
+        let failed = match focus {
+            Focus::Window(window) if window.title.is_empty() => {
+                self.untitled_apps.insert(window.app_name.clone());
+                true
+            }
+            Focus::Window(_) => false,
+            Focus::Nothing => true,
+            // Says nothing either way about access
+            Focus::Blocked => return self.missing,
+        };
+
+        if !failed {
+            if self.missing {
+                log::info!("Window access is back, resuming normal tracking");
+                let _ = app.emit("permissions-restored", ());
+            }
+            *self = Self::default();
+            return false;
+        }
+
+        self.failures += 1;
+        if !self.missing && self.failures >= PERMISSION_FAILURE_LIMIT && self.untitled_apps.len() != 1 {
+            self.missing = true;
+            log::warn!("{} window reads in a row failed; Screen Recording permission looks missing", self.failures);
+            let _ = app.emit(
+                "permissions-needed",
+                serde_json::json!({
+                    "permission": "screen_recording",
+                    "settings_url": os_integration::SCREEN_RECORDING_SETTINGS_URL
+                }),
+            );
+            if dnd::allows_notification(app, false) {
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title("LifeOS can't see your windows")
+                    .body("Allow LifeOS in System Settings › Privacy & Security › Screen Recording so it can track what you work on.")
+                    .show();
+            }
+        }
+        self.missing
+    }
+}
+
+// A monitor's bounds in the coordinates active_win_pos_rs uses: points on
+// macOS, physical pixels elsewhere
+fn monitor_bounds(monitor: &tauri::Monitor) -> (f64, f64, f64, f64) {
+    #[cfg(target_os = "macos")]
+    {
+        let scale = monitor.scale_factor();
+        let origin = monitor.position().to_logical::<f64>(scale);
+        let size = monitor.size().to_logical::<f64>(scale);
+        (origin.x, origin.y, size.width, size.height)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let origin = monitor.position();
+        let size = monitor.size();
+        (origin.x as f64, origin.y as f64, size.width as f64, size.height as f64)
+    }
+}
+
+// The display showing most of the window, with the window's bounds relative to
+// it. Monitors are enumerated on every call, so displays coming and going are
+// picked up; None if the window isn't on any of them.
+fn monitor_context<R: Runtime>(
+    app: &AppHandle<R>,
+    window: &active_win_pos_rs::WindowPosition,
+) -> Option<serde_json::Value> {
+    let monitors = app.available_monitors().ok()?;
+    let (index, monitor, (x, y, _, _)) = monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| (index, monitor, monitor_bounds(monitor)))
+        .map(|(index, monitor, bounds)| {
+            let (x, y, width, height) = bounds;
+            let overlap_x = (window.x + window.width).min(x + width) - window.x.max(x);
+            let overlap_y = (window.y + window.height).min(y + height) - window.y.max(y);
+            (index, monitor, bounds, overlap_x.max(0.0) * overlap_y.max(0.0))
+        })
+        .filter(|(_, _, _, overlap)| *overlap > 0.0)
+        .max_by(|a, b| a.3.total_cmp(&b.3))
+        .map(|(index, monitor, bounds, _)| (index, monitor, bounds))?;
+
+    Some(serde_json::json!({
+        "index": index,
+        "name": monitor.name(),
+        "x": window.x - x,
+        "y": window.y - y,
+        "width": window.width,
+        "height": window.height
+    }))
+}
+
+// Records a committed window and builds its payload. Returns the payload and
+// how long the browser URL lookup took.
+fn capture<R: Runtime>(
+    app: &AppHandle<R>,
+    window: &active_win_pos_rs::ActiveWindow,
+    state: &mut CaptureState,
+    interval_secs: u64,
+) -> (serde_json::Value, Duration) {
+    let sampled_at = chrono::Local::now();
+    let fields = settings::payload_fields(app);
+    let field_enabled = |name: &str| fields.iter().any(|f| f == name);
+
+    // Get URL if browser. Turning capture_urls off skips the browser query
+    // entirely and sends url: null; dropping the field leaves it out altogether.
+    let mut browser_url_time = Duration::ZERO;
+    let url = if field_enabled("url") && settings::get_bool(app, settings::CAPTURE_URLS, true) {
+        let started = Instant::now();
+        // When the browser can't say whether the window is private,
+        // only keep the URL if the user allowed that
+        let url = os_integration::get_browser_url(&window.app_name, &window.title)
+            .filter(|captured| {
+                captured.privacy_known
+                    || settings::get_bool(app, settings::CAPTURE_URL_WHEN_PRIVACY_UNKNOWN, false)
+            })
+            .map(|captured| privacy::sanitize_url(app, &captured.url));
+        browser_url_time = started.elapsed();
+        url
+    } else {
+        None
+    };
+
+    let identity = match &state.identity_cache {
+        Some((pid, identity)) if *pid == window.process_id => identity.clone(),
+        _ => {
+            let identity = os_integration::resolve_app(window.process_id, &window.process_path, &window.app_name);
+            state.identity_cache = Some((window.process_id, identity.clone()));
+            identity
+        }
+    };
+
+    let mut payload = serde_json::json!({
+        "app_name": window.app_name,
+        "app_id": identity.app_id,
+        "window_title": window.title,
+        "url": url
+    });
+    // Left out when they can't be resolved
+    if let Some(path) = &identity.executable_path {
+        payload["exe_path"] = path.to_string_lossy().into();
+    }
+    if let Some(bundle_id) = identity.bundle_id {
+        payload["bundle_id"] = bundle_id.into();
+    }
+
+    // Always classified, since the focus timeline needs it even when the payload doesn't
+    let rules = settings::category_rules(app);
+    let category = os_integration::classify_activity(&rules, &window.app_name, &window.title, url.as_deref());
+    app.state::<activity::Timeline>()
+        .record(&window.app_name, category, sampled_at, interval_secs);
+
+    if field_enabled("category") {
+        payload["category"] = serde_json::to_value(category).unwrap_or_default();
+    }
+
+    if field_enabled("monitor") {
+        if let Some(monitor) = monitor_context(app, &window.position) {
+            payload["monitor"] = monitor;
+        }
+    }
+
+    // Opt-in: only the change count is sampled, never clipboard contents
+    if field_enabled("clipboard_changes") && settings::get_bool(app, settings::TRACK_CLIPBOARD_CHANGES, false) {
+        payload["clipboard_changes"] = state.clipboard_monitor.changes_per_minute().into();
+    }
+
+    // Only send the core fields plus whatever the user enabled
+    if let Some(obj) = payload.as_object_mut() {
+        obj.retain(|key, _| settings::CORE_PAYLOAD_FIELDS.contains(&key.as_str()) || field_enabled(key));
+    }
+    if let Some(id) = focus::running_id(app) {
+        payload["focus_session_id"] = id.into();
+    }
+    // Only reached with a manual activity open when it doesn't suppress tracking
+    if let Some(manual) = manual_activity::current(app) {
+        payload["manual_activity"] = manual.label.into();
+    }
+
+    let changed = app
+        .state::<activity::ActivityWatch>()
+        .update(&window.app_name, &window.title, payload.clone());
+    // Let the UI follow the focused window without polling
+    if changed {
+        let _ = app.emit("activity-update", &payload);
+    }
+
+    log::debug!("Captured activity: App={}, URL={:?}", window.app_name, url);
+    (payload, browser_url_time)
+}
+
+// Starts the activity tracking loop as an async task. Does nothing if it is already running.
+pub fn start(app_handle: AppHandle) {
+    if TRACKING_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    *app_handle.state::<TrackingControl>().sender.lock().unwrap() = Some(sender);
+
+    // Stops at shutdown_all, flushing the open segment on its way out
+    background::spawn_async(&app_handle, "tracking loop", |app_handle, shutdown| {
+        run(app_handle, receiver, shutdown)
+    });
+}
+
+async fn run(app_handle: AppHandle, receiver: UnboundedReceiver<Control>, shutdown: CancellationToken) {
+    tokio::select! {
+        _ = shutdown.cancelled() => return,
+        _ = wait_for_backend(&app_handle) => {}
+    }
+
+    let paused = !app_handle.state::<TrackingEnabled>().get();
+    let mut control = LoopControl::new(receiver, shutdown.clone(), paused, tracking_interval_secs(&app_handle));
+    let mut state = CaptureState::default();
+    let mut permissions = PermissionWatch::default();
+    let mut clock = ClockCheck::default();
+    let mut idle = false;
+
+    while control.next_tick().await {
+        let iteration_start = Instant::now();
+        let mut send_time = Duration::ZERO;
+        let mut cpu_time = Duration::ZERO;
+
+        // After sleep/resume or a clock change the open segment ends at the last
+        // tick before it, rather than stretching over time nobody was tracked
+        let expected_gap = Duration::from_secs(control.interval_secs);
+        if let Some(last_tick) = clock.jumped(iteration_start, chrono::Local::now(), expected_gap) {
+            log::info!("System clock jumped or the machine slept, closing the open segment");
+            end_segment_at(&app_handle, last_tick, EndReason::Interrupted).await;
+        }
+
+        if BATTERY_THROTTLED.load(Ordering::Relaxed) || SCREEN_LOCKED.load(Ordering::Relaxed) || control.paused {
+            let label = snooze::label(&app_handle).unwrap_or_else(|| "Tracking paused".to_string());
+            tray::set_activity(&app_handle, &label);
+            end_segment(&app_handle, EndReason::Paused).await;
+            // Throttles are plain flags, so look again soon
+            control.ticks.reset_after(Duration::from_secs(1));
+            continue;
+        }
+
+        // A manual activity stands in for whatever is on screen; the open
+        // segment ends where it began
+        if manual_activity::suppresses_tracking(&app_handle) {
+            if let Some((label, since)) = manual_activity::open_since(&app_handle) {
+                tray::set_activity(&app_handle, &format!("Manual: {}", label));
+                end_segment_at(&app_handle, since, EndReason::Manual).await;
+                control.ticks.reset_after(Duration::from_secs(1));
+                continue;
+            }
+        }
+
+        // While the user is away, send a single idle update instead of
+        // repeating whatever window was left focused
+        let idle_threshold = settings::get_u64(&app_handle, settings::IDLE_THRESHOLD_SECS)
+            .unwrap_or(DEFAULT_IDLE_THRESHOLD_SECS);
+        let now_idle = measured(&mut cpu_time, os_integration::seconds_since_last_input)
+            .is_some_and(|secs| secs >= idle_threshold);
+        if now_idle != idle {
+            idle = now_idle;
+            log::info!("User is {}", if idle { "idle" } else { "active again" });
+            if idle {
+                end_segment(&app_handle, EndReason::Idle).await;
+                let mut payload = serde_json::json!({
+                    "app_name": "Idle",
+                    "window_title": "",
+                    "status": "idle"
+                });
+                stamp(&mut payload);
+                activity_queue::post(&app_handle, payload).await;
+            }
+        }
+        if idle {
+            tray::set_activity(&app_handle, "Idle");
+            control.ticks.reset_after(Duration::from_secs(1));
+            continue;
+        }
+
+        let focus = measured(&mut cpu_time, || focused_window(&app_handle));
+        let active_window_time = iteration_start.elapsed();
+        if permissions.observe(&app_handle, &focus) && matches!(focus, Focus::Nothing) {
+            // Nothing can be read until the user grants access, so only check back now and then
+            tray::set_activity(&app_handle, "Waiting for Screen Recording permission");
+            end_segment(&app_handle, EndReason::Unreadable).await;
+            control.ticks.reset_after(PERMISSION_RETRY_INTERVAL);
+            continue;
+        }
+        if let Focus::Blocked = focus {
+            tray::set_activity(&app_handle, "Focused: private");
+        }
+        let unreadable = matches!(focus, Focus::Nothing);
+        let active_window = focus.into_window();
+
+        // A newly focused window only counts once it has kept focus for
+        // min_dwell_ms, so windows passed over while alt-tabbing are never recorded
+        let mut dwell_time = Duration::ZERO;
+        if let Some(window) = &active_window {
+            let min_dwell = Duration::from_millis(min_dwell_ms(&app_handle));
+            if !min_dwell.is_zero() && !app_handle.state::<OpenSegment>().is_current(&window.app_name, &window.title) {
+                let dwell_started = Instant::now();
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(min_dwell) => {}
+                }
+                dwell_time = dwell_started.elapsed();
+                let still_focused = measured(&mut cpu_time, || focused_window(&app_handle))
+                    .into_window()
+                    .is_some_and(|now| now.app_name == window.app_name && now.title == window.title);
+                if !still_focused {
+                    log::debug!("Skipping transient window: App={}", window.app_name);
+                    control.ticks.reset_immediately();
+                    continue;
+                }
+            }
+        }
+
+        let mut browser_url_time = Duration::ZERO;
+        let segment_payload = match active_window {
+            Some(window) => {
+                let (mut payload, url_time) =
+                    measured(&mut cpu_time, || capture(&app_handle, &window, &mut state, control.interval_secs));
+                browser_url_time = url_time;
+                // User enrichers run once per segment, when a new window is first recorded
+                if !app_handle.state::<OpenSegment>().continues(&payload) {
+                    measured(&mut cpu_time, || enrichers::enrich(&app_handle, &mut payload));
+                }
+
+                let show_title = settings::payload_fields(&app_handle).iter().any(|f| f == "window_title");
+                let label = tray::focused_label(&window.app_name, show_title.then_some(window.title.as_str()));
+                tray::set_activity(&app_handle, &label);
+                Some(payload)
+            }
+            // The time still counts, as one running "Unknown" segment
+            None if unreadable => Some(unknown_payload()),
+            None => None,
+        };
+
+        if let Some(payload) = segment_payload {
+            // Unchanged windows accumulate into one segment instead of a post per tick
+            let heartbeat = Duration::from_secs(heartbeat_secs(&app_handle));
+            let record = app_handle.state::<OpenSegment>().observe(payload, heartbeat, iteration_start);
+            if let Some(record) = record {
+                tally(&app_handle, &record);
+                let send_started = Instant::now();
+                activity_queue::post(&app_handle, record).await;
+                send_time = send_started.elapsed();
+            }
+        } else {
+            // A blocked app is focused, so the open segment ends here
+            end_segment(&app_handle, EndReason::Blocked).await;
+        }
+
+        let busy = iteration_start.elapsed().saturating_sub(dwell_time);
+        app_handle.state::<TrackingOverhead>().record(OverheadSample {
+            active_window: active_window_time,
+            browser_url: browser_url_time,
+            send: send_time,
+            busy,
+            cpu: cpu_time,
+            // The next tick comes one interval after this one, or right away if this ran over
+            wall: busy.max(Duration::from_secs(control.interval_secs)),
+        });
+    }
+
+    // The open segment is left for end_session, which runs as the app exits
+    log::info!("Tracking loop stopped");
+}
+
+pub fn tracking_interval_secs<R: Runtime>(app: &AppHandle<R>) -> u64 {
+    settings::get_u64(app, settings::TRACKING_INTERVAL_SECS)
+        .filter(|secs| TRACKING_INTERVAL_RANGE.contains(secs))
+        .unwrap_or(DEFAULT_TRACKING_INTERVAL_SECS)
+}
+
+// Readiness polling before the loop starts: quick polls during normal startup,
+// then slower ones once the backend is reported as failed
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+const FAILED_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub async fn backend_ready<R: Runtime>(app: &AppHandle<R>) -> bool {
+    let client = app.state::<reqwest::Client>();
+    let response = match client.get(backend::url(backend::health_path())).send().await {
+        Ok(response) if response.status().is_success() => response,
+        _ => return false,
+    };
+    response.text().await.is_ok_and(|body| backend::is_our_health(&body))
+}
+
+// Waits until /health answers, so early activity isn't posted into the void.
+// If the backend isn't up within READY_TIMEOUT the user is told, and polling
+// carries on at a slower pace in case it recovers.
+async fn wait_for_backend<R: Runtime>(app: &AppHandle<R>) {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while Instant::now() < deadline {
+        if backend_ready(app).await {
+            return;
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL).await;
+    }
+
+    log::warn!("Backend not healthy after {:?}, holding off tracking", READY_TIMEOUT);
+    let _ = app.emit("backend-failed", READY_TIMEOUT.as_secs());
+    if dnd::allows_notification(app, true) {
+        let _ = app
+            .notification()
+            .builder()
+            .title("LifeOS coach is offline")
+            .body("The coaching backend didn't start, so activity isn't being recorded yet.")
+            .show();
+    }
+
+    while !backend_ready(app).await {
+        tokio::time::sleep(FAILED_POLL_INTERVAL).await;
+    }
+    log::info!("Backend is healthy, starting tracking");
+}
+
+// Adds when the record was captured and its sequence number, so the backend
+// doesn't have to rely on arrival time
+pub fn stamp(payload: &mut serde_json::Value) {
+    payload["captured_at"] = chrono::Local::now().to_rfc3339().into();
+    payload["seq"] = NEXT_SEQ.fetch_add(1, Ordering::Relaxed).into();
+}
+
+// Pauses tracking while running on battery below the configured
+// low_battery_pause_percent, and resumes once charging or back above it.
+// Disabled (never pauses) when the setting is unset.
+pub fn start_battery_monitor(app_handle: AppHandle) {
+    background::spawn_loop(&app_handle, "battery monitor", BATTERY_CHECK_INTERVAL, |app_handle| {
+        let threshold = settings::get_u64(app_handle, settings::LOW_BATTERY_PAUSE_PERCENT);
+        let battery = os_integration::battery_status();
+
+        let throttle = match (threshold, battery) {
+            (Some(threshold), Some(battery)) => !battery.charging && u64::from(battery.percent) < threshold,
+            _ => false,
+        };
+
+        if BATTERY_THROTTLED.swap(throttle, Ordering::SeqCst) != throttle {
+            log::info!("Low battery throttle {}", if throttle { "engaged" } else { "released" });
+            let _ = app_handle.emit(
+                "low-battery-throttle",
+                serde_json::json!({
+                    "throttled": throttle,
+                    "percent": battery.map(|b| b.percent),
+                }),
+            );
+        }
+    });
+}
+
+// Pauses tracking while the screen is locked, posting a single "locked" record
+// so the backend can close the current session, and resumes on unlock.
+// The process is suspended while the machine sleeps; a long gap between checks
+// is logged as a wake, and the lock screen shown on wake is picked up as a lock.
+pub fn start_lock_monitor(app_handle: AppHandle) {
+    let mut last_check = std::time::SystemTime::now();
+    background::spawn_loop(&app_handle, "lock monitor", LOCK_CHECK_INTERVAL, move |app_handle| {
+        let now = std::time::SystemTime::now();
+        if now
+            .duration_since(last_check)
+            .is_ok_and(|gap| gap > LOCK_CHECK_INTERVAL * 10)
+        {
+            log::info!("System woke from sleep");
+        }
+        last_check = now;
+
+        let locked = os_integration::is_screen_locked().unwrap_or(false);
+        if SCREEN_LOCKED.swap(locked, Ordering::SeqCst) != locked {
+            log::info!("Screen {}", if locked { "locked, pausing tracking" } else { "unlocked, resuming tracking" });
+            let _ = app_handle.emit("screen-locked", locked);
+            if locked {
+                flush_segment(app_handle, EndReason::Locked);
+                let mut payload = serde_json::json!({
+                    "app_name": "Locked",
+                    "window_title": "",
+                    "status": "locked"
+                });
+                stamp(&mut payload);
+                activity_queue::post_blocking(app_handle, payload);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAP: Duration = Duration::from_secs(5);
+
+    // A tick `monotonic` after the first one, with the wall clock moved by `wall`
+    fn jumped_after(monotonic: Duration, wall: chrono::Duration) -> bool {
+        let (last, last_wall) = (Instant::now(), chrono::Local::now());
+        clock_jumped((last, last_wall), (last + monotonic, last_wall + wall), GAP)
+    }
+
+    #[test]
+    fn regular_ticks_are_not_jumps() {
+        assert!(!jumped_after(GAP, chrono::Duration::seconds(5)));
+        // Scheduling jitter and small NTP corrections stay within the tolerance
+        assert!(!jumped_after(GAP + Duration::from_secs(30), chrono::Duration::seconds(40)));
+    }
+
+    #[test]
+    fn detects_the_clock_jumping_forward() {
+        assert!(jumped_after(GAP, chrono::Duration::hours(2)));
+    }
+
+    #[test]
+    fn detects_the_clock_jumping_backward() {
+        assert!(jumped_after(GAP, chrono::Duration::minutes(-30)));
+    }
+
+    #[test]
+    fn detects_a_late_tick_with_both_clocks_agreeing() {
+        // e.g. sleep on a platform where the monotonic clock keeps counting
+        assert!(jumped_after(Duration::from_secs(600), chrono::Duration::seconds(600)));
+    }
+
+    // Consumes the tick a new interval fires right away, leaving an hour until the next
+    async fn idle_control() -> (UnboundedSender<Control>, CancellationToken, LoopControl) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let shutdown = CancellationToken::new();
+        let mut control = LoopControl::new(receiver, shutdown.clone(), false, 3600);
+        assert!(control.next_tick().await);
+        (sender, shutdown, control)
+    }
+
+    async fn ticks_soon(control: &mut LoopControl) -> bool {
+        tokio::time::timeout(Duration::from_millis(200), control.next_tick()).await == Ok(true)
+    }
+
+    #[tokio::test]
+    async fn pause_resume_and_shutdown_drive_the_loop() {
+        let (sender, shutdown, mut control) = idle_control().await;
+        assert!(!ticks_soon(&mut control).await, "ticked before the interval was up");
+
+        sender.send(Control::Pause).unwrap();
+        assert!(ticks_soon(&mut control).await, "pausing didn't tick right away");
+        assert!(control.paused);
+
+        sender.send(Control::Resume).unwrap();
+        assert!(ticks_soon(&mut control).await, "resuming didn't tick right away");
+        assert!(!control.paused);
+
+        shutdown.cancel();
+        assert!(!control.next_tick().await);
+    }
+
+    #[tokio::test]
+    async fn interval_changes_apply_right_away() {
+        let (sender, _shutdown, mut control) = idle_control().await;
+        sender.send(Control::SetInterval(5)).unwrap();
+        assert!(ticks_soon(&mut control).await);
+        assert_eq!(control.interval_secs, 5);
+        assert_eq!(control.ticks.period(), Duration::from_secs(5));
+    }
+}
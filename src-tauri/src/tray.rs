@@ -1,14 +1,249 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{
-    menu::{Menu, MenuItem},
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
     Manager, Runtime,
 };
 
+use crate::{autostart, dnd, main_window, snooze, tracking};
+
+// How long the tray's snooze item pauses tracking for
+const TRAY_SNOOZE_MINUTES: u64 = 30;
+
+// The pause/resume item, kept so its label can follow the tracking state, and
+// the snooze time left it currently shows
+struct TrackingMenuItem<R: Runtime> {
+    item: MenuItem<R>,
+    snooze_minutes: Mutex<Option<u64>>,
+}
+
+fn tracking_item_label(enabled: bool) -> &'static str {
+    if enabled {
+        "Pause tracking"
+    } else {
+        "Resume tracking"
+    }
+}
+
+// Updates the pause/resume label. No-op when there is no tray (headless mode).
+pub fn sync_tracking_item<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) {
+    if let Some(item) = app.try_state::<TrackingMenuItem<R>>() {
+        let _ = item.item.set_text(tracking_item_label(enabled));
+        *item.snooze_minutes.lock().unwrap() = None;
+    }
+}
+
+// Shows the time left on a snooze in the pause/resume item. Called every
+// second; the menu is only touched when the minute changes.
+pub fn sync_snooze_item<R: Runtime>(app: &tauri::AppHandle<R>, minutes_left: u64) {
+    let Some(item) = app.try_state::<TrackingMenuItem<R>>() else {
+        return;
+    };
+    let mut shown = item.snooze_minutes.lock().unwrap();
+    if *shown == Some(minutes_left) {
+        return;
+    }
+    *shown = Some(minutes_left);
+    let _ = item
+        .item
+        .set_text(format!("Resume tracking (snoozed, {} min left)", minutes_left));
+}
+
+// The "Launch at login" checkbox
+struct AutostartMenuItem<R: Runtime>(CheckMenuItem<R>);
+
+pub fn sync_autostart_item<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) {
+    if let Some(item) = app.try_state::<AutostartMenuItem<R>>() {
+        let _ = item.0.set_checked(enabled);
+    }
+}
+
+// The "Do not disturb" checkbox
+struct DndMenuItem<R: Runtime>(CheckMenuItem<R>);
+
+pub fn sync_dnd_item<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) {
+    if let Some(item) = app.try_state::<DndMenuItem<R>>() {
+        let _ = item.0.set_checked(enabled);
+    }
+}
+
+// The tray menu and the restart item that is only shown while the backend is offline
+struct BackendStatusMenu<R: Runtime> {
+    menu: Menu<R>,
+    restart_item: MenuItem<R>,
+    // Last status shown, so repeated polls don't touch the tray
+    healthy: Mutex<Option<bool>>,
+}
+
+// Greyed-out, dimmed copy of the app icon for when the backend is offline
+fn offline_icon(icon: &Image<'_>) -> Image<'static> {
+    let rgba = icon
+        .rgba()
+        .chunks_exact(4)
+        .flat_map(|px| {
+            let grey = (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) as u8;
+            [grey, grey, grey, px[3] / 2]
+        })
+        .collect();
+    Image::new_owned(rgba, icon.width(), icon.height())
+}
+
+// Shows whether the Python backend is reachable: the icon is greyed out and the
+// tooltip reads "Coach: offline" while it isn't, and a restart item appears in the menu
+pub fn set_backend_status<R: Runtime>(app: &tauri::AppHandle<R>, healthy: bool) {
+    let Some(status) = app.try_state::<BackendStatusMenu<R>>() else {
+        return;
+    };
+    if status.healthy.lock().unwrap().replace(healthy) == Some(healthy) {
+        return;
+    }
+
+    if let Some(tray) = app.tray_by_id("tray") {
+        if let Some(icon) = app.default_window_icon() {
+            let icon = if healthy { icon.clone() } else { offline_icon(icon) };
+            let _ = tray.set_icon(Some(icon));
+        }
+    }
+    refresh_tooltip(app);
+
+    if healthy {
+        let _ = status.menu.remove(&status.restart_item);
+    } else {
+        let _ = status.menu.insert(&status.restart_item, 0);
+    }
+}
+
+// Tooltip changes closer together than this are held back, so alt-tabbing
+// doesn't churn the tray
+const TOOLTIP_DEBOUNCE: Duration = Duration::from_secs(2);
+// Longest window title shown in the tooltip
+const TOOLTIP_TITLE_CHARS: usize = 40;
+
+// What the tooltip says the coach currently sees
+#[derive(Default)]
+struct ActivityTooltip(Mutex<Option<(String, Instant)>>);
+
+// The tooltip text: the backend being offline trumps the current activity
+fn refresh_tooltip<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let Some(tray) = app.tray_by_id("tray") else {
+        return;
+    };
+    let healthy = app
+        .try_state::<BackendStatusMenu<R>>()
+        .and_then(|status| *status.healthy.lock().unwrap());
+    let activity = app
+        .try_state::<ActivityTooltip>()
+        .and_then(|tooltip| tooltip.0.lock().unwrap().as_ref().map(|(text, _)| text.clone()));
+
+    let text = match (healthy, activity) {
+        (Some(false), _) => "Coach: offline".to_string(),
+        (_, Some(activity)) => activity,
+        _ => "Coach: connected".to_string(),
+    };
+    let _ = tray.set_tooltip(Some(text));
+}
+
+// "Focused: {app} — {title}", with the title shortened. Pass no title when the
+// user doesn't share window titles.
+pub fn focused_label(app_name: &str, title: Option<&str>) -> String {
+    match title.map(str::trim).filter(|title| !title.is_empty()) {
+        Some(title) if title.chars().count() > TOOLTIP_TITLE_CHARS => {
+            let short: String = title.chars().take(TOOLTIP_TITLE_CHARS - 1).collect();
+            format!("Focused: {} — {}…", app_name, short.trim_end())
+        }
+        Some(title) => format!("Focused: {} — {}", app_name, title),
+        None => format!("Focused: {}", app_name),
+    }
+}
+
+// Shows what the tracking loop currently sees in the tray tooltip. Called every
+// tick; a change arriving within TOOLTIP_DEBOUNCE of the last is picked up on a
+// later tick. No-op without a tray.
+pub fn set_activity<R: Runtime>(app: &tauri::AppHandle<R>, activity: &str) {
+    let Some(tooltip) = app.try_state::<ActivityTooltip>() else {
+        return;
+    };
+    {
+        let mut shown = tooltip.0.lock().unwrap();
+        match shown.as_ref() {
+            Some((text, _)) if text == activity => return,
+            Some((_, at)) if at.elapsed() < TOOLTIP_DEBOUNCE => return,
+            _ => {}
+        }
+        *shown = Some((activity.to_string(), Instant::now()));
+    }
+    refresh_tooltip(app);
+}
+
 pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
     let quit_i = MenuItem::with_id(app, "quit", "Quit LifeOS", true, None::<&str>)?;
     let show_i = MenuItem::with_id(app, "show", "Open Dashboard", true, None::<&str>)?;
+    let tracking_enabled = app.state::<tracking::TrackingEnabled>().get();
+    let tracking_i = MenuItem::with_id(
+        app,
+        "toggle_tracking",
+        tracking_item_label(tracking_enabled),
+        true,
+        None::<&str>,
+    )?;
     
-    let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+    let snooze_i = MenuItem::with_id(
+        app,
+        "snooze_tracking",
+        format!("Snooze tracking for {} minutes", TRAY_SNOOZE_MINUTES),
+        true,
+        None::<&str>,
+    )?;
+
+    let restart_i = MenuItem::with_id(
+        app,
+        "restart_backend",
+        "Backend: offline — click to restart",
+        true,
+        None::<&str>,
+    )?;
+    
+    let restart_now_i = MenuItem::with_id(app, "restart_backend_now", "Restart backend", true, None::<&str>)?;
+
+    let logs_i = MenuItem::with_id(app, "open_logs", "Open logs folder", true, None::<&str>)?;
+    let data_i = MenuItem::with_id(app, "open_data", "Open data folder", true, None::<&str>)?;
+
+    let autostart_i = CheckMenuItem::with_id(
+        app,
+        "toggle_autostart",
+        "Launch at login",
+        true,
+        autostart::is_registered(),
+        None::<&str>,
+    )?;
+    
+    let dnd_i = CheckMenuItem::with_id(
+        app,
+        "toggle_dnd",
+        "Do not disturb",
+        true,
+        dnd::state(app).enabled,
+        None::<&str>,
+    )?;
+
+    let menu = Menu::with_items(
+        app,
+        &[&show_i, &tracking_i, &snooze_i, &dnd_i, &autostart_i, &restart_now_i, &logs_i, &data_i, &quit_i],
+    )?;
+    app.manage(TrackingMenuItem {
+        item: tracking_i,
+        snooze_minutes: Mutex::new(None),
+    });
+    app.manage(AutostartMenuItem(autostart_i));
+    app.manage(DndMenuItem(dnd_i));
+    app.manage(ActivityTooltip::default());
+    app.manage(BackendStatusMenu {
+        menu: menu.clone(),
+        restart_item: restart_i,
+        healthy: Mutex::new(None),
+    });
 
     let _tray = TrayIconBuilder::with_id("tray")
         .menu(&menu)
@@ -18,25 +253,63 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
                 app.exit(0);
             }
             "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
+                main_window::show(app);
+            }
+            "restart_backend" | "restart_backend_now" => {
+                // Restarting waits for the old process to exit, so keep it off the event loop
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = crate::restart_backend_process(&app) {
+                        log::error!("Failed to restart Python backend: {}", e);
+                    }
+                });
+            }
+            "open_logs" => {
+                if let Err(e) = crate::open_app_dir(app.path().app_log_dir(), "log") {
+                    log::error!("Failed to open logs folder: {}", e);
+                }
+            }
+            "open_data" => {
+                if let Err(e) = crate::open_app_dir(app.path().app_data_dir(), "data") {
+                    log::error!("Failed to open data folder: {}", e);
+                }
+            }
+            "toggle_tracking" => {
+                let enabled = app.state::<tracking::TrackingEnabled>().get();
+                if let Err(e) = tracking::set_enabled(app, !enabled) {
+                    log::error!("Failed to save tracking state: {}", e);
+                }
+            }
+            "snooze_tracking" => {
+                if let Err(e) = snooze::snooze(app, TRAY_SNOOZE_MINUTES) {
+                    log::error!("Failed to snooze tracking: {}", e);
+                }
+            }
+            "toggle_dnd" => {
+                let enabled = !dnd::state(app).enabled;
+                if let Err(e) = dnd::set(app, enabled, None) {
+                    log::error!("Failed to change do not disturb: {}", e);
+                    sync_dnd_item(app, !enabled);
+                }
+            }
+            "toggle_autostart" => {
+                // The checkbox has already flipped itself; set_enabled puts it back on failure
+                let enabled = !autostart::is_registered();
+                if let Err(e) = autostart::set_enabled(app, enabled) {
+                    log::error!("Failed to change launch at login: {}", e);
+                    sync_autostart_item(app, !enabled);
                 }
             }
             _ => {}
         })
-        .on_tray_icon_event(|tray, event| match event {
-            TrayIconEvent::Click {
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
                 button: MouseButton::Left,
                 ..
-            } => {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
+            } = event
+            {
+                main_window::show(tray.app_handle());
             }
-            _ => {}
         })
         .build(app)?;
 
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::background;
+
+// Kept in its own store file, like the activity queue, so it doesn't bloat settings.json
+const USAGE_FILE: &str = "usage_summary.json";
+const USAGE_KEY: &str = "today";
+
+// How often the day's usage is written to disk when it changed
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+// Spans on the same app this close together are joined into one
+const MAX_JOIN_GAP_MS: i64 = 2000;
+
+// Time on one app, from one or more back-to-back segments. Times are Unix ms.
+#[derive(Clone, Serialize, Deserialize)]
+struct UsageSpan {
+    app_name: String,
+    category: Option<String>,
+    start_ms: i64,
+    end_ms: i64,
+}
+
+impl UsageSpan {
+    // Parses a segment record; records without a duration (e.g. idle markers) are skipped
+    fn from_record(record: &Value) -> Option<Self> {
+        let app_name = record.get("app_name")?.as_str()?.to_string();
+        let start_ms = DateTime::parse_from_rfc3339(record.get("start")?.as_str()?).ok()?.timestamp_millis();
+        let duration_secs = record.get("duration_secs")?.as_f64()?;
+        Some(Self {
+            app_name,
+            category: record.get("category").and_then(Value::as_str).map(str::to_string),
+            start_ms,
+            end_ms: start_ms + (duration_secs * 1000.0) as i64,
+        })
+    }
+
+    // Whether `next` picks up where this span left off on the same app
+    fn continues_into(&self, next: &UsageSpan) -> bool {
+        self.app_name == next.app_name
+            && self.category == next.category
+            && (next.start_ms - self.end_ms).abs() <= MAX_JOIN_GAP_MS
+    }
+
+    // Seconds of this span at or after `since_ms`
+    fn seconds_since(&self, since_ms: i64) -> u64 {
+        (self.end_ms - self.start_ms.max(since_ms)).max(0) as u64 / 1000
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct UsageDay {
+    // Local date, YYYY-MM-DD
+    day: Option<String>,
+    spans: Vec<UsageSpan>,
+}
+
+// Seconds on one app (and category, when categories are captured)
+#[derive(Serialize)]
+pub struct UsageEntry {
+    pub app_name: String,
+    pub category: Option<String>,
+    pub seconds: u64,
+}
+
+#[derive(Serialize)]
+pub struct UsageReport {
+    pub since: String,
+    pub total_seconds: u64,
+    // Most used first
+    pub apps: Vec<UsageEntry>,
+}
+
+// Today's time per app, tallied locally from the tracking loop's segments so the
+// UI can show "top apps today" without asking the backend. Cleared at local midnight.
+#[derive(Default)]
+pub struct UsageSummary {
+    inner: Mutex<UsageDay>,
+    dirty: AtomicBool,
+}
+
+fn today() -> (String, DateTime<Local>) {
+    let now = Local::now();
+    let midnight = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .unwrap_or(now);
+    (now.format("%Y-%m-%d").to_string(), midnight)
+}
+
+impl UsageSummary {
+    // Restores today's usage saved by an earlier run; anything from another day is dropped
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let summary = Self::default();
+        let saved = app
+            .store(USAGE_FILE)
+            .ok()
+            .and_then(|store| store.get(USAGE_KEY))
+            .and_then(|value| serde_json::from_value::<UsageDay>(value).ok());
+        let (day, _) = today();
+        if let Some(saved) = saved.filter(|saved| saved.day.as_ref() == Some(&day)) {
+            log::info!("Restored today's usage ({} spans)", saved.spans.len());
+            *summary.inner.lock().unwrap() = saved;
+        }
+        summary
+    }
+
+    // Adds a finished segment record. Time before today's midnight is left out.
+    pub fn add(&self, record: &Value) {
+        let Some(mut span) = UsageSpan::from_record(record) else {
+            return;
+        };
+        let (day, midnight) = today();
+        let midnight_ms = midnight.timestamp_millis();
+        if span.end_ms <= midnight_ms {
+            return;
+        }
+        span.start_ms = span.start_ms.max(midnight_ms);
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.day.as_ref() != Some(&day) {
+            inner.day = Some(day);
+            inner.spans.clear();
+        }
+        // Heartbeats split one stretch on an app into several records; keep them as one
+        match inner.spans.last_mut() {
+            Some(last) if last.continues_into(&span) => last.end_ms = last.end_ms.max(span.end_ms),
+            _ => inner.spans.push(span),
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    // Usage from `since` (default: local midnight) until now, including the
+    // segment still open. Only today is kept, so earlier times count from midnight.
+    pub fn report(&self, since: Option<DateTime<Local>>, open: Option<&Value>) -> UsageReport {
+        let (day, midnight) = today();
+        let since = since.map_or(midnight, |since| since.max(midnight));
+        let since_ms = since.timestamp_millis();
+
+        let mut totals: HashMap<(String, Option<String>), u64> = HashMap::new();
+        let inner = self.inner.lock().unwrap();
+        let spans: &[UsageSpan] = if inner.day.as_ref() == Some(&day) { &inner.spans } else { &[] };
+        for span in spans.iter().cloned().chain(open.and_then(UsageSpan::from_record)) {
+            let seconds = span.seconds_since(since_ms);
+            if seconds > 0 {
+                *totals.entry((span.app_name, span.category)).or_default() += seconds;
+            }
+        }
+        drop(inner);
+
+        let mut apps: Vec<UsageEntry> = totals
+            .into_iter()
+            .map(|((app_name, category), seconds)| UsageEntry {
+                app_name,
+                category,
+                seconds,
+            })
+            .collect();
+        apps.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.app_name.cmp(&b.app_name)));
+        UsageReport {
+            since: since.to_rfc3339(),
+            total_seconds: apps.iter().map(|entry| entry.seconds).sum(),
+            apps,
+        }
+    }
+
+    // Writes the day's usage to disk if it changed since the last write
+    pub fn persist<R: Runtime>(&self, app: &AppHandle<R>) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let day = match serde_json::to_value(&*self.inner.lock().unwrap()) {
+            Ok(day) => day,
+            Err(e) => {
+                log::warn!("Failed to serialize usage summary: {}", e);
+                return;
+            }
+        };
+        let Ok(store) = app.store(USAGE_FILE) else {
+            log::warn!("Failed to open usage summary store");
+            return;
+        };
+        store.set(USAGE_KEY, day);
+        if let Err(e) = store.save() {
+            log::warn!("Failed to save usage summary: {}", e);
+        }
+    }
+}
+
+// Saves the usage summary every PERSIST_INTERVAL so it survives a restart within the day
+pub fn start_persister<R: Runtime>(app: AppHandle<R>) {
+    background::spawn_loop(&app, "usage persister", PERSIST_INTERVAL, |app| app.state::<UsageSummary>().persist(app));
+}
@@ -1,5 +1,6 @@
 import { useState, useEffect } from 'react';
-import { User, Bell, Shield, Smartphone, HardDrive, Cpu, Activity, LogOut } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { User, Bell, Shield, Smartphone, HardDrive, Cpu, Activity, LogOut, Globe } from 'lucide-react';
 import { useAuth } from '../contexts/AuthContext';
 import { GlassCard } from './GlassCard';
 
@@ -7,6 +8,23 @@ export function Settings() {
     const { user, signOut } = useAuth();
     const [activeNudge, setActiveNudge] = useState<string | null>(null);
     const [systemMetrics, setSystemMetrics] = useState<any>(null);
+    const [captureUrls, setCaptureUrls] = useState(true);
+
+    useEffect(() => {
+        invoke<boolean>('get_capture_urls')
+            .then(setCaptureUrls)
+            .catch((e) => console.error("Failed to read URL capture setting", e));
+    }, []);
+
+    const toggleCaptureUrls = async () => {
+        const enabled = !captureUrls;
+        try {
+            await invoke('set_capture_urls', { enabled });
+            setCaptureUrls(enabled);
+        } catch (e) {
+            console.error("Failed to change URL capture setting", e);
+        }
+    };
 
     useEffect(() => {
         // Poll for active nudge status
@@ -133,6 +151,26 @@ export function Settings() {
                                 <span className="translate-x-1 inline-block h-4 w-4 transform rounded-full bg-white transition" />
                             </div>
                         </div>
+
+                        <div className="flex items-center justify-between">
+                            <div className="flex items-center gap-3">
+                                <div className="p-2 bg-zinc-800 rounded-lg text-zinc-400">
+                                    <Globe size={20} />
+                                </div>
+                                <div>
+                                    <p className="font-medium text-white">Capture URLs</p>
+                                    <p className="text-sm text-zinc-400">Record which websites you visit, not just the browser</p>
+                                </div>
+                            </div>
+                            <button
+                                role="switch"
+                                aria-checked={captureUrls}
+                                onClick={toggleCaptureUrls}
+                                className={`relative inline-flex h-6 w-11 items-center rounded-full transition-colors ${captureUrls ? 'bg-indigo-600' : 'bg-zinc-700'}`}
+                            >
+                                <span className={`${captureUrls ? 'translate-x-6' : 'translate-x-1'} inline-block h-4 w-4 transform rounded-full bg-white transition`} />
+                            </button>
+                        </div>
                     </div>
                 </GlassCard>
             </div>